@@ -0,0 +1,145 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use crate::types::*;
+
+/// Strategy for the messages sent by the Byzantine nodes, which replaces `DistributedAlgorithm::send`
+/// for them. The Byzantine nodes still receive messages and update their states using the
+/// algorithm, but they are not required to stop. The strategy sees the states of all nodes at the
+/// beginning of the round, and it may send arbitrary messages to any subset of the ports. The
+/// neighbors receive the absent marker for the other ports, which is therefore required for
+/// Byzantine nodes, see `Message::absent`. For example, a strategy claiming to be matched to every
+/// neighbor that has not stopped yet:
+///
+/// ```
+/// use pn_simulator::{DaSimulator, Outbox, State};
+/// use pn_simulator::adversary::{Adversary, View};
+/// use pn_simulator::algorithms::{BipartiteMaximalMatching, BpMessage, BpState};
+/// use pn_simulator::generators;
+///
+/// struct Liar;
+///
+/// impl Adversary<BpState, BpMessage> for Liar {
+///     fn send(&self, view: &View<BpState>, outbox: &mut Outbox<BpMessage>) {
+///         for (port, v) in view.network[view.node].iter().enumerate() {
+///             if !view.states[*v].is_output() {
+///                 outbox.send(port, BpMessage::Matched);
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut simulator = DaSimulator::builder(BipartiteMaximalMatching)
+///     .network(&generators::cycle(6))
+///     .byzantine(&[0], Liar)
+///     .build()
+///     .unwrap();
+///
+/// assert!(simulator.run().success());
+/// ```
+pub trait Adversary<S: State, M: Message>: Send + Sync {
+    /// Send the messages of the given Byzantine node in the given round
+    fn send(&self, view: &View<S>, outbox: &mut Outbox<M>);
+}
+
+/// Global view of the network given to an adversary, see `Adversary`
+pub struct View<'a, S: State> {
+    /// Index of the Byzantine node sending the messages
+    pub node: usize,
+    /// Communication round, starting from 1
+    pub round: u32,
+    /// States of all nodes at the beginning of the round by node index. Nodes that have already
+    /// finished, e.g. due to crashing, are shown in their final states.
+    pub states: &'a [S],
+    /// Neighbors of every node by port by node index, see `DaSimulator::ports`
+    pub network: &'a [Vec<usize>],
+    /// Whether each node is Byzantine by node index
+    pub byzantine: &'a [bool],
+}
+
+/// Strategy where the Byzantine nodes send nothing at all
+pub struct Silent;
+
+impl<S: State, M: Message> Adversary<S, M> for Silent {
+    fn send(&self, _: &View<S>, _: &mut Outbox<M>) {}
+}
+
+/// Strategy where the Byzantine nodes send independent random messages to every port, generated
+/// by the given function. The generator is seeded by the seed, the node and the round, so the
+/// messages do not depend on the scheduling.
+pub struct RandomMessages<M> {
+    pub generate: fn(&mut StdRng) -> M,
+    pub seed: u64,
+}
+
+impl<S: State, M: Message> Adversary<S, M> for RandomMessages<M> {
+    fn send(&self, view: &View<S>, outbox: &mut Outbox<M>) {
+        let mut rng = StdRng::seed_from_u64(self.seed ^ (view.node as u64) << 32 ^ view.round as u64);
+        (0..outbox.ports()).for_each(|p| outbox.send(p, (self.generate)(&mut rng)));
+    }
+}
+
+/// Strategy where the Byzantine nodes tell different neighbors different things: port `p` gets
+/// message `p mod n` of the given `n` messages in every round
+pub struct Equivocating<M> {
+    pub messages: Vec<M>,
+}
+
+impl<S: State, M: Message + Clone + Sync> Adversary<S, M> for Equivocating<M> {
+    fn send(&self, _: &View<S>, outbox: &mut Outbox<M>) {
+        assert!(!self.messages.is_empty(), "no messages to equivocate with");
+        (0..outbox.ports()).for_each(|p| outbox.send(p, self.messages[p % self.messages.len()].clone()));
+    }
+}
+
+/// States of all nodes by round shared between the node threads, from which the views of the
+/// adversaries are composed
+pub(crate) struct Board<S> {
+    // States at the beginning of each round and whether the node has finished by node index
+    nodes: Mutex<Vec<(Vec<S>, bool)>>,
+    condvar: Condvar,
+}
+
+impl<S: State> Board<S> {
+    pub fn new(node_count: usize) -> Self {
+        Self {
+            nodes: Mutex::new((0..node_count).map(|_| (Vec::new(), false)).collect()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Record the state of the given node at the beginning of its next round
+    pub fn publish(&self, node: usize, state: &S) {
+        self.nodes.lock().unwrap()[node].0.push(state.clone());
+        self.condvar.notify_all();
+    }
+
+    /// Mark the given node as finished, its last published state is final
+    pub fn finish(&self, node: usize) {
+        self.nodes.lock().unwrap()[node].1 = true;
+        self.condvar.notify_all();
+    }
+
+    /// Wait for every node to begin the given round or finish, returning the states at the
+    /// beginning of the round. Every node reaches the round without the messages of that round,
+    /// so waiting cannot deadlock. `None` if the deadline is reached.
+    pub fn view(&self, round: u32, deadline: Instant) -> Option<Vec<S>> {
+        let ready = |n: &Vec<(Vec<S>, bool)>| n.iter().all(|(h, done)| *done || h.len() >= round as usize);
+        let mut nodes = self.nodes.lock().unwrap();
+        while !ready(&nodes) {
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            nodes = self.condvar.wait_timeout(nodes, timeout).unwrap().0;
+        }
+
+        Some(nodes.iter().map(|(h, _)| h.get(round as usize - 1).or(h.last()).expect("no state published").clone()).collect())
+    }
+}
@@ -11,6 +11,6 @@ mod isomorphic;
 mod mvc_3approx;
 
 // Re-exports to allow direct access to the algorithms
-pub use bipartite::BipartiteMaximalMatching;
-pub use isomorphic::IsomorphicNeighborhood;
-pub use mvc_3approx::Mvc3approx;
+pub use bipartite::{BipartiteMaximalMatching, BpMessage, BpState};
+pub use isomorphic::{InMessage, InState, IsomorphicNeighborhood};
+pub use mvc_3approx::{Mvc3approx, Mvc3approxMessage, Mvc3approxState};
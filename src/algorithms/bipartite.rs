@@ -6,15 +6,17 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::{fmt, iter};
+use std::fmt;
 use std::collections::HashSet;
 use super::bipartite::NodeColor::*;
 use super::bipartite::MatchingState::*;
-use crate::types::{Input, Message, DistributedAlgorithm, State};
+use crate::types::{Input, Message, DistributedAlgorithm, Outbox, Output, State};
+use crate::verifier::Problem;
 
 /// Bipartite maximal matching algorithm in the PN model. **WARNING:** Requires that the input
 /// network is bipartite wrt. even/odd nodes! Even nodes will be marked `White` and odd nodes
 /// `Black` to establish the two partitions.
+#[derive(Default)]
 pub struct BipartiteMaximalMatching;
 
 /// Helper enum for tracking node color
@@ -27,7 +29,7 @@ enum NodeColor {
 impl From<u32> for NodeColor {
     fn from(i: u32) -> Self {
         // Even nodes are white and odd nodes are black
-        match i % 2 == 0 {
+        match i.is_multiple_of(2) {
             true => White,
             false => Black,
         }
@@ -70,23 +72,46 @@ pub struct BpState {
 }
 
 impl BpState {
-    // Helper for the Minimum Vertex Cover 3-Approximation algorithm to determine final node state
+    // Whether the node is matched over some port, running or stopped. Also used by the Minimum
+    // Vertex Cover 3-Approximation algorithm to determine its node state.
     pub fn matched(&self) -> bool {
-        match self.matching_state {
-            Ms(_) => true,
-            _ => false,
-        }
+        matches!(self.matching_state, Mr(_) | Ms(_))
     }
 }
 
 impl State for BpState {
+    // Whether each incident edge is part of the matching
+    type Output = bool;
+
     fn is_output(&self) -> bool {
+        matches!(self.matching_state, Us | Ms(_))
+    }
+
+    fn output(&self) -> Option<Output<bool>> {
+        match self.matching_state {
+            Us => Some(Output::Edges(vec![false; self.degree as usize])),
+            Ms(i) => Some(Output::Edges((0..self.degree).map(|p| p == i).collect())),
+            _ => None,
+        }
+    }
+
+    fn dot_attributes(&self) -> String {
+        match self.matched() {
+            true => "style = filled fillcolor = \"lightblue\"".into(),
+            false => String::new(),
+        }
+    }
+
+    fn highlighted_ports(&self) -> Vec<u32> {
         match self.matching_state {
-            Us => true,
-            Ms(_) => true,
-            _ => false
+            Mr(i) | Ms(i) => vec![i],
+            _ => Vec::new(),
         }
     }
+
+    fn in_solution(&self) -> Option<bool> {
+        Some(self.matched()) // The solution size is twice the size of the matching
+    }
 }
 
 impl fmt::Debug for BpState {
@@ -110,17 +135,26 @@ pub enum BpMessage {
     Matched,
 }
 
-impl Message for BpMessage {}
+impl Message for BpMessage {
+    fn is_noop(&self) -> bool {
+        *self == BpMessage::Noop
+    }
 
-impl DistributedAlgorithm<BpState, BpMessage> for BipartiteMaximalMatching {
-    // Boxing is required here since we return different implementors of this iterator
-    type MsgIter = Box<dyn Iterator<Item=BpMessage>>;
+    fn absent() -> Option<Self> {
+        Some(BpMessage::Noop) // A crashed neighbor neither proposes nor accepts
+    }
+}
 
-    fn name() -> String {
+impl DistributedAlgorithm<BpState, BpMessage> for BipartiteMaximalMatching {
+    fn name(&self) -> String {
         "Bipartite Maximal Matching".into()
     }
 
-    fn init(info: &Input) -> BpState {
+    fn problem(&self) -> Option<Problem> {
+        Some(Problem::MaximalMatching)
+    }
+
+    fn init(&self, info: &Input) -> BpState {
         let degree = info.node_degree;
         let color = info.node_id.into();
 
@@ -140,28 +174,29 @@ impl DistributedAlgorithm<BpState, BpMessage> for BipartiteMaximalMatching {
         }
     }
 
-    fn send(state: &BpState) -> Self::MsgIter {
+    fn send(&self, state: &BpState, outbox: &mut Outbox<BpMessage>) {
         // Match each of the states of the negotiation process separately. Look at the destructured
         // values to determine the conditions of taking the branch. By default, send `Noop` messages
         // to all neighbors.
         match state {
             BpState { degree, color: White, round, matching_state: Ur, .. } if round % 2 == 0 && round / 2 < *degree => {
                 // In the original algorithm this is implemented using 2k, here we divide instead
-                let r = round / 2;
-                Box::new((0..).map(move |i| if i == r { BpMessage::Proposal } else { BpMessage::Noop }))
+                outbox.send((round / 2) as usize, BpMessage::Proposal);
             }
             BpState { color: White, round, matching_state: Mr(_), .. } if round % 2 == 0 => {
-                Box::new(iter::repeat(BpMessage::Matched))
+                outbox.broadcast(BpMessage::Matched);
             }
             BpState { color: Black, round, matching_state: Ur, m_set, .. } if round % 2 != 0 && !m_set.is_empty() => {
-                let m = *m_set.iter().min().unwrap();
-                Box::new((0..).map(move |i| if i == m { BpMessage::Accept } else { BpMessage::Noop }))
+                outbox.send(*m_set.iter().min().unwrap() as usize, BpMessage::Accept);
             }
-            _ => Box::new(iter::repeat(BpMessage::Noop))
+            _ => ()
         }
+
+        // Fill the remaining ports with `Noop` messages
+        outbox.fill(BpMessage::Noop);
     }
 
-    fn receive(state: &BpState, messages: impl Iterator<Item=BpMessage>) -> BpState {
+    fn receive(&self, state: &BpState, messages: impl Iterator<Item=BpMessage>) -> BpState {
         // Initialize the resulting state as a copy of the current one
         let mut result = state.clone();
         result.round += 1; // Increment the round counter
@@ -6,31 +6,51 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::{fmt, iter};
-use crate::{Input, Message, DistributedAlgorithm, State};
+use std::fmt;
+use crate::{Input, Message, DistributedAlgorithm, Outbox, Output, State};
 
-/// Isomorphic neighborhood gathering algorithm up to depth D in the PN model. This algorithm it is
-/// just a functional test and does nothing useful, but it can be used to answer a certain quiz :)
-pub struct IsomorphicNeighborhood<const D: u32>;
+/// Isomorphic neighborhood gathering algorithm up to a given depth in the PN model. This algorithm
+/// it is just a functional test and does nothing useful, but it can be used to answer a certain
+/// quiz :)
+pub struct IsomorphicNeighborhood {
+    depth: u32,
+}
 
-/// Node state for the Isomorphic Neighborhood algorithm. Variant format: `Count(<rounds>, <sum>)`
+impl IsomorphicNeighborhood {
+    /// Construct an instance of the algorithm that gathers the neighborhood up to the given depth
+    pub fn new(depth: u32) -> Self {
+        Self { depth }
+    }
+}
+
+/// Node state for the Isomorphic Neighborhood algorithm. Variant format:
+/// `Count(<rounds>, <sum>, <depth>)`, where the target depth is carried along to determine stopping.
 #[derive(Clone, PartialEq)]
-pub enum InState<const D: u32> {
-    Count(u32, u32),
+pub enum InState {
+    Count(u32, u32, u32),
 }
 
-impl<const D: u32> State for InState<D> {
+impl State for InState {
+    // The sum gathered from the neighborhood
+    type Output = u32;
+
     fn is_output(&self) -> bool {
         match self {
-            InState::Count(i, _) => *i == D, // Target depth reached
+            InState::Count(i, _, d) => i == d, // Target depth reached
+        }
+    }
+
+    fn output(&self) -> Option<Output<u32>> {
+        match *self {
+            InState::Count(_, n, _) => self.is_output().then_some(Output::Node(n)),
         }
     }
 }
 
-impl<const D: u32> fmt::Debug for InState<D> {
+impl fmt::Debug for InState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            InState::Count(_, n) => write!(f, "{n}")
+            InState::Count(_, n, _) => write!(f, "{n}")
         }
     }
 }
@@ -43,35 +63,32 @@ pub enum InMessage {
 
 impl Message for InMessage {}
 
-impl<const D: u32> DistributedAlgorithm<InState<D>, InMessage> for IsomorphicNeighborhood<D> {
-    // `impl` convenience requires #![feature(type_alias_impl_trait)] and nightly Rust for now
-    type MsgIter = impl Iterator<Item=InMessage>;
-
-    fn name() -> String {
-        format!("Isomorphic Neighborhood (depth {D})")
+impl DistributedAlgorithm<InState, InMessage> for IsomorphicNeighborhood {
+    fn name(&self) -> String {
+        format!("Isomorphic Neighborhood (depth {})", self.depth)
     }
 
-    fn init(info: &Input) -> InState<D> {
-        InState::Count(0, info.node_degree) // Initialize sum to node degree
+    fn init(&self, info: &Input) -> InState {
+        InState::Count(0, info.node_degree, self.depth) // Initialize sum to node degree
     }
 
-    fn send(state: &InState<D>) -> Self::MsgIter {
+    fn send(&self, state: &InState, outbox: &mut Outbox<InMessage>) {
         match *state {
             // All neighbors get the same number
-            InState::Count(_, n) => Box::new(iter::repeat(InMessage::Number(n)))
+            InState::Count(_, n, _) => outbox.broadcast(InMessage::Number(n))
         }
     }
 
-    fn receive(state: &InState<D>, messages: impl Iterator<Item=InMessage>) -> InState<D> {
+    fn receive(&self, state: &InState, messages: impl Iterator<Item=InMessage>) -> InState {
         match state {
-            InState::Count(i, _) => {
+            InState::Count(i, _, d) => {
                 if state.is_output() {
                     state.clone() // Don't transition to another state if we've stopped
                 } else {
                     // Next round with a sum of the numbers from all neighbors
                     InState::Count(i + 1, messages.map(|m| match m {
                         InMessage::Number(n) => n
-                    }).sum())
+                    }).sum(), *d)
                 }
             }
         }
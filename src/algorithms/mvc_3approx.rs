@@ -8,11 +8,16 @@
 
 use std::fmt;
 use super::bipartite::{BipartiteMaximalMatching, BpMessage, BpState};
-use crate::types::{Input, Message, DistributedAlgorithm, State};
+use crate::types::{Input, Message, DistributedAlgorithm, Outbox, Output, State};
+use crate::verifier::Problem;
 
 /// Minimum vertex cover 3-approximation algorithm in the PN model. Leverages the Bipartite Maximal
 /// Matching algorithm in a virtual bipartite network configuration.
-pub struct Mvc3approx {}
+#[derive(Default)]
+pub struct Mvc3approx {
+    // The underlying algorithm run by both virtual nodes
+    matching: BipartiteMaximalMatching,
+}
 
 /// Node state for the MVC 3-approx. algorithm. Tracks the states of both virtual nodes.
 #[derive(Clone, PartialEq)]
@@ -22,9 +27,34 @@ pub struct Mvc3approxState {
 }
 
 impl State for Mvc3approxState {
+    // Whether the node is part of the vertex cover
+    type Output = bool;
+
     fn is_output(&self) -> bool {
         self.s1.is_output() && self.s2.is_output() // Require both instances to be stopped
     }
+
+    fn output(&self) -> Option<Output<bool>> {
+        self.is_output().then(|| Output::Node(self.s1.matched() || self.s2.matched()))
+    }
+
+    fn dot_attributes(&self) -> String {
+        match self.s1.matched() || self.s2.matched() {
+            true => "style = filled fillcolor = \"lightblue\"".into(),
+            false => String::new(),
+        }
+    }
+
+    fn highlighted_ports(&self) -> Vec<u32> {
+        // Highlight the edges matched in either of the virtual networks
+        let mut ports = self.s1.highlighted_ports();
+        ports.extend(self.s2.highlighted_ports());
+        ports
+    }
+
+    fn in_solution(&self) -> Option<bool> {
+        Some(self.s1.matched() || self.s2.matched())
+    }
 }
 
 impl fmt::Debug for Mvc3approxState {
@@ -41,43 +71,60 @@ pub struct Mvc3approxMessage {
     m2: BpMessage,
 }
 
-impl Message for Mvc3approxMessage {}
+impl Message for Mvc3approxMessage {
+    fn is_noop(&self) -> bool {
+        self.m1.is_noop() && self.m2.is_noop()
+    }
 
-impl DistributedAlgorithm<Mvc3approxState, Mvc3approxMessage> for Mvc3approx {
-    // `impl` convenience requires #![feature(type_alias_impl_trait)] and nightly Rust for now
-    type MsgIter = impl Iterator<Item=Mvc3approxMessage>;
+    fn absent() -> Option<Self> {
+        Some(Self { m1: BpMessage::Noop, m2: BpMessage::Noop })
+    }
+}
 
-    fn name() -> String {
+impl DistributedAlgorithm<Mvc3approxState, Mvc3approxMessage> for Mvc3approx {
+    fn name(&self) -> String {
         "Minimum Vertex Cover 3-Approximation".into()
     }
 
-    fn init(info: &Input) -> Mvc3approxState {
+    fn problem(&self) -> Option<Problem> {
+        Some(Problem::VertexCover)
+    }
+
+    fn init(&self, info: &Input) -> Mvc3approxState {
         Mvc3approxState {
-            s1: BipartiteMaximalMatching::init(&Input {
+            s1: self.matching.init(&Input {
                 node_id: 0, // "Even" nodes
                 ..*info
             }),
-            s2: BipartiteMaximalMatching::init(&Input {
+            s2: self.matching.init(&Input {
                 node_id: 1, // "Odd" nodes
                 ..*info
             }),
         }
     }
 
-    fn send(state: &Mvc3approxState) -> Self::MsgIter {
+    fn send(&self, state: &Mvc3approxState, outbox: &mut Outbox<Mvc3approxMessage>) {
+        // Run the send function for both virtual nodes into separate buffers
+        let (mut o1, mut o2) = (Outbox::new(outbox.ports()), Outbox::new(outbox.ports()));
+        self.matching.send(&state.s1, &mut o1);
+        self.matching.send(&state.s2, &mut o2);
+
         // Swap the messages during sending to make the virtual network bipartite
-        BipartiteMaximalMatching::send(&state.s1).zip(BipartiteMaximalMatching::send(&state.s2))
-            .map(|(m2, m1)| Mvc3approxMessage { m1, m2 })
+        o1.into_messages()
+            .into_iter()
+            .zip(o2.into_messages())
+            .enumerate()
+            .for_each(|(i, (m2, m1))| outbox.send(i, Mvc3approxMessage { m1, m2 }));
     }
 
-    fn receive(state: &Mvc3approxState, messages: impl Iterator<Item=Mvc3approxMessage>) -> Mvc3approxState {
+    fn receive(&self, state: &Mvc3approxState, messages: impl Iterator<Item=Mvc3approxMessage>) -> Mvc3approxState {
         // Receive the joined messages for both virtual network partitions and unzip them
         let (m1, m2): (Vec<_>, Vec<_>) = messages.map(|m| (m.m1, m.m2)).unzip();
 
         // Generate a new state by running the receive function for both virtual nodes individually
         Mvc3approxState {
-            s1: BipartiteMaximalMatching::receive(&state.s1, m1.into_iter()),
-            s2: BipartiteMaximalMatching::receive(&state.s2, m2.into_iter()),
+            s1: self.matching.receive(&state.s1, m1.into_iter()),
+            s2: self.matching.receive(&state.s2, m2.into_iter()),
         }
     }
 }
@@ -0,0 +1,313 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::adversary::{Adversary, Silent};
+use crate::faults::{Corruption, Crashes, MessageFault, MessageFaults, Mutators};
+use crate::network::{self, IdAssignment, PortNumbering};
+use crate::observer::Observer;
+use crate::simulator::DaSimulator;
+use crate::types::*;
+
+/// Function for determining the size of a message in bits, see `SimulatorBuilder::message_size`
+pub type MessageSize<M> = fn(&M) -> u32;
+
+/// Algorithm-independent options of a simulation, see `SimulatorBuilder` for the details
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Timeout for deadlock prevention, 5 seconds by default
+    pub timeout: Duration,
+    /// Communication round limit, `None` runs until natural termination
+    pub round_limit: Option<u32>,
+    /// Port numbering strategy applied to the network
+    pub ports: PortNumbering,
+    /// Node ID assignment strategy applied to the network
+    pub ids: IdAssignment,
+    /// Seed for the random strategies
+    pub seed: u64,
+    /// Local inputs of the nodes by node index, after applying the ID assignment
+    pub inputs: Inputs,
+    /// CONGEST bandwidth limit in bits per message, requires a message size function
+    pub bandwidth: Option<u32>,
+    /// Record the states and messages of every round, see `DaSimulator::record_trace`
+    pub trace: bool,
+    /// Random delays before sending and receiving, see `Jitter`
+    pub jitter: Option<Jitter>,
+    /// Crash faults of the nodes by node index, after applying the ID assignment
+    pub crashes: Crashes,
+    /// Faults of individual messages by node index, after applying the ID assignment
+    pub message_faults: MessageFaults,
+    /// Byzantine nodes by node index, after applying the ID assignment. The adversary sends their
+    /// messages, see `SimulatorBuilder::byzantine`.
+    pub byzantine: Vec<usize>,
+}
+
+/// Random delays injected into the node threads for perturbing the scheduling. The nodes start in
+/// a random order, and every node sleeps for a random duration of at most `max` before sending and
+/// before receiving in every round. The results of a run must not depend on the scheduling, see
+/// `DaSimulator::check_scheduling`.
+#[derive(Clone, Copy, Debug)]
+pub struct Jitter {
+    /// Maximum duration of a single delay
+    pub max: Duration,
+    /// Seed for the start order and the delays
+    pub seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            round_limit: None,
+            ports: PortNumbering::Given,
+            ids: IdAssignment::Given,
+            seed: 0,
+            inputs: Inputs::new(),
+            bandwidth: None,
+            trace: false,
+            jitter: None,
+            crashes: Crashes::None,
+            message_faults: MessageFaults::default(),
+            byzantine: Vec::new(),
+        }
+    }
+}
+
+/// Builder for configuring a `DaSimulator`, created using `DaSimulator::builder`. The combination
+/// of the options is validated when building the simulator. For example:
+///
+/// ```
+/// use std::time::Duration;
+/// use pn_simulator::DaSimulator;
+/// use pn_simulator::algorithms::Mvc3approx;
+/// use pn_simulator::generators;
+/// use pn_simulator::network::PortNumbering;
+///
+/// let mut simulator = DaSimulator::builder(Mvc3approx::default())
+///     .network(&generators::cycle(10))
+///     .timeout(Duration::from_secs(1))
+///     .round_limit(100)
+///     .port_numbering(PortNumbering::Random)
+///     .seed(42)
+///     .build()
+///     .unwrap();
+///
+/// assert!(simulator.run().success());
+/// ```
+pub struct SimulatorBuilder<A: DistributedAlgorithm<S, M>, S: State, M: Message> {
+    algorithm: A,
+    edges: Option<Vec<(u32, u32)>>,
+    options: Options,
+    message_size: Option<MessageSize<M>>,
+    mutators: Mutators<M>,
+    adversary: Option<Box<dyn Adversary<S, M>>>,
+    observers: Vec<Arc<dyn Observer<S, M>>>,
+    types: PhantomData<fn() -> S>,
+}
+
+impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> SimulatorBuilder<A, S, M> {
+    /// Create a builder for simulating the given algorithm instance with the default options
+    pub fn new(algorithm: A) -> Self {
+        Self {
+            algorithm,
+            edges: None,
+            options: Options::default(),
+            message_size: None,
+            mutators: Mutators::default(),
+            adversary: None,
+            observers: Vec::new(),
+            types: PhantomData,
+        }
+    }
+
+    /// Build the network from the given set of edges, the order of which determines the port
+    /// numbering unless overridden by `port_numbering`
+    pub fn network(mut self, edges: &[(u32, u32)]) -> Self {
+        self.edges = Some(edges.to_vec());
+        self
+    }
+
+    /// Replace all the algorithm-independent options at once
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the timeout for deadlock prevention
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// Terminate the simulation after the given number of communication rounds
+    pub fn round_limit(mut self, rounds: u32) -> Self {
+        self.options.round_limit = Some(rounds);
+        self
+    }
+
+    /// Set the port numbering strategy, see `PortNumbering`
+    pub fn port_numbering(mut self, ports: PortNumbering) -> Self {
+        self.options.ports = ports;
+        self
+    }
+
+    /// Set the node ID assignment strategy, see `IdAssignment`
+    pub fn id_assignment(mut self, ids: IdAssignment) -> Self {
+        self.options.ids = ids;
+        self
+    }
+
+    /// Set the seed for the random strategies
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = seed;
+        self
+    }
+
+    /// Set the local inputs of the nodes by node index, after applying the ID assignment
+    pub fn inputs(mut self, inputs: Inputs) -> Self {
+        self.options.inputs = inputs;
+        self
+    }
+
+    /// Limit the size of every message to the given number of bits as in the CONGEST model. Sending
+    /// a larger message is treated as a panic of the algorithm, see `RunResult::panic`. Requires
+    /// `message_size`.
+    pub fn bandwidth(mut self, bits: u32) -> Self {
+        self.options.bandwidth = Some(bits);
+        self
+    }
+
+    /// Set the function for determining the size of a message in bits, which is also used for
+    /// counting the bits sent during a run, see `RunResult::bits`
+    pub fn message_size(mut self, size: MessageSize<M>) -> Self {
+        self.message_size = Some(size);
+        self
+    }
+
+    /// Record the states and messages of every round, see `DaSimulator::record_trace`
+    pub fn trace(mut self, enabled: bool) -> Self {
+        self.options.trace = enabled;
+        self
+    }
+
+    /// Perturb the scheduling of the node threads with random delays of at most `max`, see `Jitter`
+    pub fn jitter(mut self, max: Duration, seed: u64) -> Self {
+        self.options.jitter = Some(Jitter { max, seed });
+        self
+    }
+
+    /// Make nodes crash according to the given schedule or at random, see `Crashes`
+    pub fn crashes(mut self, crashes: Crashes) -> Self {
+        self.options.crashes = crashes;
+        self
+    }
+
+    /// Drop, duplicate or corrupt individual messages, see `MessageFaults`. The messages are
+    /// required to be cloneable for duplicating them.
+    pub fn message_faults(mut self, faults: MessageFaults) -> Self where M: Clone {
+        self.options.message_faults = faults;
+        self.mutators.copy = Some(M::clone);
+        self
+    }
+
+    /// Set the function for corrupting messages, required for the corruption faults given in
+    /// `message_faults`
+    pub fn corruption(mut self, corrupt: Corruption<M>) -> Self {
+        self.mutators.corrupt = Some(corrupt);
+        self
+    }
+
+    /// Mark the given nodes as Byzantine, replacing their `DistributedAlgorithm::send` by the given
+    /// adversary, see `Adversary`. Byzantine nodes are not required to stop for the run to succeed.
+    /// Nodes given in the options without an adversary are `Silent`.
+    pub fn byzantine(mut self, nodes: &[usize], adversary: impl Adversary<S, M> + 'static) -> Self {
+        self.options.byzantine = nodes.to_vec();
+        self.adversary = Some(Box::new(adversary));
+        self
+    }
+
+    /// Register an observer for instrumenting the simulation, can be called multiple times. Keep a
+    /// clone of the `Arc` for inspecting the observer after running the simulation.
+    pub fn observer(mut self, observer: Arc<dyn Observer<S, M>>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Validate the options and construct the simulator
+    pub fn build(self) -> Result<DaSimulator<A, S, M>, String> {
+        let mut edges = self.edges.ok_or("no network given")?;
+        let options = self.options;
+
+        if edges.is_empty() {
+            return Err("the network has no edges".into());
+        }
+
+        // The simulator requires a simple graph, i.e. no self-loops or parallel edges
+        let mut uniq = HashSet::new();
+        for &(a, b) in &edges {
+            if a == b {
+                return Err(format!("self-loop at node {a}, the network must be simple"));
+            }
+
+            if !uniq.insert((a.min(b), a.max(b))) {
+                return Err(format!("parallel edges between nodes {a} and {b}, the network must be simple"));
+            }
+        }
+
+        let node_count = 1 + edges.iter().map(|(a, b)| *a.max(b)).max().unwrap_or(0);
+        if let Some(i) = options.inputs.keys().find(|i| **i >= node_count) {
+            return Err(format!("local input given for node {i}, but the network only has {node_count} nodes"));
+        }
+
+        options.crashes.validate(node_count as usize)?;
+        if options.crashes.rounds(node_count as usize).iter().any(Option::is_some) && M::absent().is_none() {
+            return Err("crash faults require an absent marker for the messages, see `Message::absent`".into());
+        }
+
+        if let Some(v) = options.byzantine.iter().find(|v| **v >= node_count as usize) {
+            return Err(format!("node {v} given as Byzantine, but the network only has {node_count} nodes"));
+        }
+
+        if !options.byzantine.is_empty() && M::absent().is_none() {
+            return Err("Byzantine nodes require an absent marker for the messages, see `Message::absent`".into());
+        }
+
+        if options.timeout.is_zero() {
+            return Err("the timeout must be positive".into());
+        }
+
+        if options.round_limit == Some(0) {
+            return Err("the round limit must be positive, use no limit to run until termination".into());
+        }
+
+        if options.bandwidth.is_some() && self.message_size.is_none() {
+            return Err("a CONGEST bandwidth limit requires a message size function".into());
+        }
+
+        network::apply_strategies(&mut edges, options.ports, options.ids, options.seed);
+        options.message_faults.validate(&edges)?;
+
+        if options.message_faults.includes(MessageFault::Drop) && M::absent().is_none() {
+            return Err("dropping messages requires an absent marker for them, see `Message::absent`".into());
+        }
+
+        if options.message_faults.includes(MessageFault::Duplicate) && self.mutators.copy.is_none() {
+            return Err("duplicating messages requires them to be cloneable, see `message_faults`".into());
+        }
+
+        if options.message_faults.includes(MessageFault::Corrupt) && self.mutators.corrupt.is_none() {
+            return Err("corrupting messages requires a corruption function".into());
+        }
+
+        let adversary = self.adversary.unwrap_or_else(|| Box::new(Silent));
+        Ok(DaSimulator::construct(self.algorithm, &edges, &options, self.message_size, self.mutators, adversary, self.observers))
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::builder::Options;
+use crate::network::{self, IdAssignment, PortNumbering};
+use crate::optimum::Approximation;
+use crate::registry::{self, Params};
+
+/// Source of a network in an experiment
+#[derive(Clone, Debug)]
+pub enum Source {
+    /// Network read from a file, see `network::read_edges`
+    File(PathBuf),
+    /// Network built by a generator, see `network::generate`
+    Generator(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Generator(spec) => write!(f, "{spec}"),
+        }
+    }
+}
+
+/// Matrix of simulations to run, every algorithm is run on every network for every combination of
+/// seed, port numbering and ID assignment
+pub struct Experiment {
+    /// Algorithms given as `<name>` or `<name>:<param>=<value>,...`
+    pub algorithms: Vec<String>,
+    pub networks: Vec<Source>,
+    pub seeds: Vec<u64>,
+    pub ports: Vec<PortNumbering>,
+    pub ids: Vec<IdAssignment>,
+    /// Timeout for deadlock prevention in every simulation
+    pub timeout: Duration,
+    /// Communication round limit for every simulation, `None` runs until natural termination
+    pub round_limit: Option<u32>,
+}
+
+/// Outcome of a single simulation of an experiment
+pub struct Row {
+    pub algorithm: String,
+    pub network: String,
+    pub seed: u64,
+    pub ports: PortNumbering,
+    pub ids: IdAssignment,
+    pub nodes: usize,
+    pub edges: usize,
+    pub rounds: u32,
+    /// Node-averaged stopping round, see `RunResult::average_stopping_round`
+    pub average_round: Option<f64>,
+    pub messages: u64,
+    /// Number of no-op messages, see `Message::is_noop`
+    pub noops: u64,
+    pub stopped: usize,
+    pub status: Status,
+    /// Number of nodes in the solution, see `State::in_solution`
+    pub solution: Option<usize>,
+    /// Whether the solution is valid, `None` if the algorithm does not declare a problem or some
+    /// nodes did not stop, see `DaSimulator::verify`
+    pub valid: Option<bool>,
+    /// Solution compared to the optimum, see `DaSimulator::approximation`. The objective value is
+    /// given in the unit of the optimum, e.g. in edges for matchings, unlike `solution`.
+    pub approximation: Option<Approximation>,
+}
+
+/// Termination status of a single simulation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// All nodes reached a stopping state
+    Success,
+    /// The round limit was reached before all nodes stopped
+    RoundLimit,
+    /// Some nodes did not stop before the timeout
+    Timeout,
+    /// The algorithm panicked in some node, see `RunResult::panic`
+    Panic,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Success => write!(f, "success"),
+            Status::RoundLimit => write!(f, "round-limit"),
+            Status::Timeout => write!(f, "timeout"),
+            Status::Panic => write!(f, "panic"),
+        }
+    }
+}
+
+/// Column names of the result table
+const COLUMNS: [&str; 18] = [
+    "algorithm", "network", "seed", "ports", "ids", "nodes", "edges", "rounds", "avg_round",
+    "messages", "noops", "stopped", "status", "solution", "valid", "value", "optimum", "ratio",
+];
+
+impl Row {
+    /// Format the fields of the row in the order of `COLUMNS`
+    fn fields(&self) -> [String; 18] {
+        [
+            self.algorithm.clone(),
+            self.network.clone(),
+            self.seed.to_string(),
+            self.ports.to_string(),
+            self.ids.to_string(),
+            self.nodes.to_string(),
+            self.edges.to_string(),
+            self.rounds.to_string(),
+            self.average_round.map_or(String::new(), |r| format!("{r:.2}")),
+            self.messages.to_string(),
+            self.noops.to_string(),
+            self.stopped.to_string(),
+            self.status.to_string(),
+            self.solution.map_or(String::new(), |s| s.to_string()),
+            self.valid.map_or(String::new(), |v| v.to_string()),
+            self.approximation.map_or(String::new(), |a| a.value.to_string()),
+            self.approximation.map_or(String::new(), |a| a.optimum.to_string()),
+            self.approximation.map_or(String::new(), |a| format!("{:.3}", a.ratio())),
+        ]
+    }
+}
+
+impl Experiment {
+    /// Total number of simulations in the experiment
+    pub fn size(&self) -> usize {
+        self.algorithms.len() * self.networks.len() * self.seeds.len() * self.ports.len() * self.ids.len()
+    }
+
+    /// Run all the simulations of the experiment one after another, reporting the progress to
+    /// stderr. Errors in the configuration (unknown algorithms, invalid parameters or networks)
+    /// are reported before running anything: all networks are built and every simulation is set
+    /// up once before the first run.
+    pub fn run(&self) -> Result<Vec<Row>, String> {
+        let algorithms = self.algorithms
+            .iter()
+            .map(|spec| {
+                let (name, params) = parse_algorithm(spec)?;
+                let entry = registry::lookup(name).ok_or_else(|| format!("unknown algorithm: {name}"))?;
+                Ok((spec, entry, params))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Random generators depend on the seed, so the network is built once per seed
+        let mut networks = Vec::with_capacity(self.networks.len() * self.seeds.len());
+        for source in &self.networks {
+            for &seed in &self.seeds {
+                let edges = match source {
+                    Source::File(path) => network::read_edges(path).map_err(|e| format!("{source}: {e}"))?,
+                    Source::Generator(spec) => network::generate(spec, seed)?,
+                };
+                networks.push((source, seed, edges));
+            }
+        }
+
+        let options = |seed, ports, ids| Options {
+            timeout: self.timeout,
+            round_limit: self.round_limit,
+            ports,
+            ids,
+            seed,
+            ..Options::default()
+        };
+
+        // Set up every simulation once without running it, so that e.g. invalid algorithm
+        // parameters for the last network are not only detected after running everything else
+        for (source, seed, edges) in &networks {
+            for &ports in &self.ports {
+                for &ids in &self.ids {
+                    for (_, entry, params) in &algorithms {
+                        entry.build(edges, &options(*seed, ports, ids), params)
+                            .map_err(|e| format!("{source}: {e}"))?;
+                    }
+                }
+            }
+        }
+
+        let mut rows = Vec::with_capacity(self.size());
+        for (source, seed, edges) in &networks {
+            let seed = *seed;
+            for &ports in &self.ports {
+                for &ids in &self.ids {
+                    for (spec, entry, params) in &algorithms {
+                        eprintln!("[{}/{}] {spec} on {source} (seed {seed}, ports {ports}, ids {ids})",
+                                  rows.len() + 1, self.size());
+
+                        let mut simulator = entry.build(edges, &options(seed, ports, ids), params)
+                            .map_err(|e| format!("{source}: {e}"))?;
+                        let result = simulator.run();
+                        let status = match result.success() {
+                            true => Status::Success,
+                            false if result.panic.is_some() => Status::Panic,
+                            false if self.round_limit.is_some_and(|l| result.rounds >= l) => Status::RoundLimit,
+                            false => Status::Timeout,
+                        };
+
+                        rows.push(Row {
+                            algorithm: spec.to_string(),
+                            network: source.to_string(),
+                            seed,
+                            ports,
+                            ids,
+                            nodes: result.nodes,
+                            edges: edges.len(),
+                            rounds: result.rounds,
+                            average_round: result.average_stopping_round(),
+                            messages: result.messages,
+                            noops: result.noops,
+                            stopped: result.stopped,
+                            status,
+                            solution: simulator.solution_size(),
+                            valid: simulator.verify().filter(|_| result.complete()).map(|v| v.is_ok()),
+                            approximation: simulator.approximation(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Find the worst approximation ratio achieved by each algorithm over the given rows, in order of
+/// first appearance. Returns the algorithm and the row with the worst ratio.
+pub fn worst_ratios(rows: &[Row]) -> Vec<(&str, &Row)> {
+    let mut worst: Vec<(&str, &Row)> = Vec::new();
+    for row in rows {
+        let Some(ratio) = row.approximation.map(|a| a.ratio()) else { continue };
+        match worst.iter_mut().find(|(a, _)| *a == row.algorithm) {
+            Some((_, w)) if w.approximation.is_some_and(|a| a.ratio() < ratio) => *w = row,
+            Some(_) => (),
+            None => worst.push((&row.algorithm, row)),
+        }
+    }
+
+    worst
+}
+
+/// Parse an algorithm given as `<name>` or `<name>:<param>=<value>,...`
+fn parse_algorithm(spec: &str) -> Result<(&str, Params), String> {
+    let (name, params) = spec.split_once(':').unwrap_or((spec, ""));
+    let params = params
+        .split(',')
+        .filter(|p| !p.is_empty())
+        .map(|p| p
+            .split_once('=')
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .ok_or_else(|| format!("expected name=value in {spec}, got {p}")))
+        .collect::<Result<_, _>>()?;
+
+    Ok((name, params))
+}
+
+/// Write the results as CSV with a header row
+pub fn write_csv(out: &mut impl Write, rows: &[Row]) -> io::Result<()> {
+    // Quote fields containing separators, e.g. generator specifications with several parameters
+    let quote = |f: &str| match f.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", f.replace('"', "\"\"")),
+        false => f.to_string(),
+    };
+
+    writeln!(out, "{}", COLUMNS.join(","))?;
+    for row in rows {
+        writeln!(out, "{}", row.fields().map(|f| quote(&f)).join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Write the results as a Markdown table
+pub fn write_markdown(out: &mut impl Write, rows: &[Row]) -> io::Result<()> {
+    writeln!(out, "| {} |", COLUMNS.join(" | "))?;
+    writeln!(out, "|{}", "---|".repeat(COLUMNS.len()))?;
+    for row in rows {
+        writeln!(out, "| {} |", row.fields().map(|f| f.replace('|', "\\|")).join(" | "))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::AddAssign;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::index;
+
+/// Function for corrupting a message, see `MessageFaults`. The generator is seeded by the seed of
+/// the message faults, so that the corruption is reproducible.
+pub type Corruption<M> = fn(M, &mut StdRng) -> M;
+
+/// Crash faults of the nodes. A node crashing in round `r` completes rounds `1..r` normally, but
+/// from round `r` on it sends nothing and never receives again, and its state is frozen. Instead of
+/// the messages of a crashed neighbor, the nodes receive the absent marker of the message type,
+/// which is required for crash faults, see `Message::absent`. Crashed nodes are not required to
+/// stop for the run to succeed, see `RunResult::crashes`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Crashes {
+    /// No node crashes
+    #[default]
+    None,
+    /// The given nodes crash in the given rounds, given as pairs of node index and round
+    Schedule(Vec<(usize, u32)>),
+    /// The given number of distinct random nodes crash, each in a random round between 1 and
+    /// `max_round`, both inclusive
+    Random { count: usize, max_round: u32, seed: u64 },
+}
+
+impl Crashes {
+    /// Check that the crashes are applicable to a network with the given number of nodes
+    pub fn validate(&self, node_count: usize) -> Result<(), String> {
+        match self {
+            Crashes::None => Ok(()),
+            Crashes::Schedule(schedule) => match schedule.iter().find(|(v, r)| *v >= node_count || *r == 0) {
+                Some((v, _)) if *v >= node_count => Err(format!("crash given for node {v}, but the network only has {node_count} nodes")),
+                Some((v, _)) => Err(format!("crash of node {v} given for round 0, the rounds start from 1")),
+                None => Ok(()),
+            },
+            Crashes::Random { count, max_round, .. } => match (*count > node_count, *max_round == 0) {
+                (true, _) => Err(format!("{count} crashes given, but the network only has {node_count} nodes")),
+                (_, true) => Err("the maximum crash round must be positive".into()),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// Resolve the round in which each node crashes by node index, `None` for nodes that do not
+    /// crash. If the schedule lists a node multiple times, the earliest round applies.
+    pub fn rounds(&self, node_count: usize) -> Vec<Option<u32>> {
+        let mut rounds = vec![None; node_count];
+        match self {
+            Crashes::None => {}
+            Crashes::Schedule(schedule) => schedule.iter().for_each(|&(v, r)| {
+                rounds[v] = Some(rounds[v].map_or(r, |c: u32| c.min(r)));
+            }),
+            Crashes::Random { count, max_round, seed } => {
+                let mut rng = StdRng::seed_from_u64(*seed);
+                for v in index::sample(&mut rng, node_count, *count) {
+                    rounds[v] = Some(rng.gen_range(1..=*max_round));
+                }
+            }
+        }
+
+        rounds
+    }
+}
+
+/// Kind of fault affecting a single message, see `MessageFaults`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MessageFault {
+    /// The message is lost, the receiver gets the absent marker instead, see `Message::absent`
+    Drop,
+    /// The message is delivered twice, once in its round and again in the following round in
+    /// place of the message sent over the same port then, which is lost
+    Duplicate,
+    /// The message is replaced by the result of the corruption function, see `Corruption`
+    Corrupt,
+}
+
+impl fmt::Display for MessageFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageFault::Drop => write!(f, "drop"),
+            MessageFault::Duplicate => write!(f, "duplicate"),
+            MessageFault::Corrupt => write!(f, "corrupt"),
+        }
+    }
+}
+
+/// Faults of individual messages, substituted between sending and receiving. Every message is
+/// affected by a fault with the given probabilities independently, and additionally the scheduled
+/// faults affect exactly the given messages. The random faults are drawn by every node for its
+/// own ports in order, so they do not depend on the scheduling of the node threads.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageFaults {
+    /// Probability of dropping a message
+    pub drop: f64,
+    /// Probability of duplicating a message, requires cloneable messages
+    pub duplicate: f64,
+    /// Probability of corrupting a message, requires a corruption function
+    pub corrupt: f64,
+    /// Edges to which the random faults are restricted given as pairs of node indices in either
+    /// order, all edges if empty
+    pub edges: Vec<(usize, usize)>,
+    /// Faults of the message sent from one node to another in the given round, given as tuples of
+    /// the sending node index, the receiving node index, the round and the fault
+    pub schedule: Vec<(usize, usize, u32, MessageFault)>,
+    /// Seed for the random faults and the corruption function
+    pub seed: u64,
+}
+
+impl MessageFaults {
+    /// Check that the faults are applicable to a network with the given edges
+    pub fn validate(&self, edges: &[(u32, u32)]) -> Result<(), String> {
+        let probabilities = [self.drop, self.duplicate, self.corrupt];
+        if probabilities.iter().any(|p| !(0.0..=1.0).contains(p)) || probabilities.iter().sum::<f64>() > 1.0 {
+            return Err("the message fault probabilities must be between 0 and 1 and sum up to at most 1".into());
+        }
+
+        let exists = |a: usize, b: usize| edges.iter().any(|&(u, v)| (u as usize, v as usize) == (a, b) || (v as usize, u as usize) == (a, b));
+        if let Some((a, b)) = self.edges.iter().find(|(a, b)| !exists(*a, *b)) {
+            return Err(format!("message faults given for edge {a}-{b}, which is not in the network"));
+        }
+
+        match self.schedule.iter().find(|(a, b, r, _)| !exists(*a, *b) || *r == 0) {
+            Some((a, b, 0, _)) => Err(format!("message fault from {a} to {b} given for round 0, the rounds start from 1")),
+            Some((a, b, ..)) => Err(format!("message fault given for edge {a}-{b}, which is not in the network")),
+            None => Ok(()),
+        }
+    }
+
+    /// Determines if any message may be affected by a fault
+    pub fn any(&self) -> bool {
+        [MessageFault::Drop, MessageFault::Duplicate, MessageFault::Corrupt].into_iter().any(|f| self.includes(f))
+    }
+
+    /// Determines if any message may be affected by the given kind of fault
+    pub fn includes(&self, fault: MessageFault) -> bool {
+        let p = match fault {
+            MessageFault::Drop => self.drop,
+            MessageFault::Duplicate => self.duplicate,
+            MessageFault::Corrupt => self.corrupt,
+        };
+
+        p > 0.0 || self.schedule.iter().any(|s| s.3 == fault)
+    }
+}
+
+/// Number of messages affected by each kind of fault during a run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FaultCounts {
+    pub dropped: u64,
+    pub duplicated: u64,
+    pub corrupted: u64,
+}
+
+impl FaultCounts {
+    /// Total number of faulty messages
+    pub fn total(&self) -> u64 {
+        self.dropped + self.duplicated + self.corrupted
+    }
+}
+
+impl AddAssign<MessageFault> for FaultCounts {
+    fn add_assign(&mut self, fault: MessageFault) {
+        match fault {
+            MessageFault::Drop => self.dropped += 1,
+            MessageFault::Duplicate => self.duplicated += 1,
+            MessageFault::Corrupt => self.corrupted += 1,
+        }
+    }
+}
+
+impl AddAssign for FaultCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.dropped += other.dropped;
+        self.duplicated += other.duplicated;
+        self.corrupted += other.corrupted;
+    }
+}
+
+impl Sum for FaultCounts {
+    fn sum<I: Iterator<Item=Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |mut a, b| {
+            a += b;
+            a
+        })
+    }
+}
+
+/// Functions on the messages needed for duplicating and corrupting them
+pub(crate) struct Mutators<M> {
+    pub copy: Option<fn(&M) -> M>,
+    pub corrupt: Option<Corruption<M>>,
+}
+
+// Manual implementations needed to avoid `Clone` and `Copy` dependencies on `M`
+impl<M> Clone for Mutators<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Mutators<M> {}
+
+impl<M> Default for Mutators<M> {
+    fn default() -> Self {
+        Self { copy: None, corrupt: None }
+    }
+}
+
+/// Injects the message faults into the messages sent by a single node
+pub(crate) struct Injector<M> {
+    probabilities: [f64; 3],
+    // Whether the random faults apply to each port
+    affected: Vec<bool>,
+    // Scheduled faults by port and round
+    scheduled: HashMap<(usize, u32), MessageFault>,
+    // Duplicated messages to deliver again in the next round by port
+    replays: Vec<Option<M>>,
+    mutators: Mutators<M>,
+    rng: StdRng,
+}
+
+impl<M> Injector<M> {
+    /// Create an injector for the given node with the given neighbors by port, using the given seed
+    /// that is specific to the node
+    pub fn new(faults: &MessageFaults, node: usize, neighbors: &[usize], mutators: Mutators<M>, seed: u64) -> Self {
+        let on_edge = |(a, b): &(usize, usize), v: usize| (*a, *b) == (node, v) || (*b, *a) == (node, v);
+        let port = |v: usize| neighbors.iter().position(|n| *n == v);
+
+        Self {
+            probabilities: [faults.drop, faults.duplicate, faults.corrupt],
+            affected: neighbors.iter().map(|v| faults.edges.is_empty() || faults.edges.iter().any(|e| on_edge(e, *v))).collect(),
+            scheduled: faults.schedule
+                .iter()
+                .filter(|(from, ..)| *from == node)
+                .filter_map(|&(_, to, round, fault)| port(to).map(|p| ((p, round), fault)))
+                .collect(),
+            replays: neighbors.iter().map(|_| None).collect(),
+            mutators,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Apply the faults to the messages sent in the given round by port, where `None` stands for
+    /// an absent message. Returns the faults applied by port.
+    pub fn apply(&mut self, round: u32, messages: &mut [Option<M>]) -> Vec<(usize, MessageFault)> {
+        let mut faults = Vec::new();
+        for (port, message) in messages.iter_mut().enumerate() {
+            // A pending duplicate replaces the message sent in this round
+            if let Some(m) = self.replays[port].take() {
+                *message = Some(m);
+            }
+
+            // The random fault is drawn regardless of the schedule to keep the draws aligned
+            let x: f64 = self.rng.gen();
+            let random = match self.probabilities {
+                [d, ..] if x < d => Some(MessageFault::Drop),
+                [d, u, _] if x < d + u => Some(MessageFault::Duplicate),
+                [d, u, c] if x < d + u + c => Some(MessageFault::Corrupt),
+                _ => None,
+            };
+
+            let fault = self.scheduled.get(&(port, round)).copied().or(random.filter(|_| self.affected[port]));
+            let Some(fault) = fault.filter(|_| message.is_some()) else {
+                continue;
+            };
+
+            match fault {
+                MessageFault::Drop => *message = None,
+                MessageFault::Duplicate => {
+                    let copy = self.mutators.copy.expect("no function for duplicating messages");
+                    self.replays[port] = message.as_ref().map(copy);
+                }
+                MessageFault::Corrupt => {
+                    let corrupt = self.mutators.corrupt.expect("no function for corrupting messages");
+                    *message = message.take().map(|m| corrupt(m, &mut self.rng));
+                }
+            }
+
+            faults.push((port, fault));
+        }
+
+        faults
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashSet;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+/// Edge set of a network in the format accepted by `DaSimulator::from_network`. Nodes are numbered
+/// from zero, and the order of the edges determines the port numbering in the simulator.
+pub type EdgeSet = Vec<(u32, u32)>;
+
+/// Path with `n` nodes: `0 - 1 - ... - (n - 1)`
+pub fn path(n: u32) -> EdgeSet {
+    assert!(n >= 2, "a path needs at least 2 nodes");
+    (1..n).map(|i| (i - 1, i)).collect()
+}
+
+/// Cycle with `n` nodes: a path that additionally connects node `n - 1` back to node `0`
+pub fn cycle(n: u32) -> EdgeSet {
+    assert!(n >= 3, "a simple cycle needs at least 3 nodes");
+    let mut edges = path(n);
+    edges.push((0, n - 1));
+    edges
+}
+
+/// Star with node `0` in the center and `leaves` leaf nodes numbered from `1` onwards
+pub fn star(leaves: u32) -> EdgeSet {
+    assert!(leaves >= 1, "a star needs at least 1 leaf");
+    (1..=leaves).map(|i| (0, i)).collect()
+}
+
+/// Complete graph with `n` nodes
+pub fn complete(n: u32) -> EdgeSet {
+    assert!(n >= 2, "a complete graph needs at least 2 nodes");
+    (0..n).flat_map(|a| (a + 1..n).map(move |b| (a, b))).collect()
+}
+
+/// Complete bipartite graph `K_{a,b}`. The first partition consists of nodes `0..a` and the second
+/// one of nodes `a..a + b`.
+pub fn complete_bipartite(a: u32, b: u32) -> EdgeSet {
+    assert!(a >= 1 && b >= 1, "both partitions need at least 1 node");
+    (0..a).flat_map(|i| (a..a + b).map(move |j| (i, j))).collect()
+}
+
+/// Grid of `width * height` nodes, node `(x, y)` has index `y * width + x`
+pub fn grid(width: u32, height: u32) -> EdgeSet {
+    assert!(width * height >= 2, "a grid needs at least 2 nodes");
+    let index = |x, y| y * width + x;
+    let mut edges = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if x + 1 < width {
+                edges.push((index(x, y), index(x + 1, y)));
+            }
+            if y + 1 < height {
+                edges.push((index(x, y), index(x, y + 1)));
+            }
+        }
+    }
+
+    edges
+}
+
+/// Torus of `width * height` nodes, i.e. a grid with wrap-around edges in both dimensions. Both
+/// dimensions must be at least 3 for the resulting graph to be simple.
+pub fn torus(width: u32, height: u32) -> EdgeSet {
+    assert!(width >= 3 && height >= 3, "torus dimensions must be at least 3");
+    let index = |x, y| y * width + x;
+
+    (0..height)
+        .flat_map(|y| (0..width).flat_map(move |x| [
+            (index(x, y), index((x + 1) % width, y)),
+            (index(x, y), index(x, (y + 1) % height)),
+        ]))
+        .collect()
+}
+
+/// Hypercube of dimension `d` with `2^d` nodes, nodes are adjacent if their indices differ in
+/// exactly one bit
+pub fn hypercube(d: u32) -> EdgeSet {
+    assert!((1..32).contains(&d), "hypercube dimension must be between 1 and 31");
+    (0..1u32 << d)
+        .flat_map(|i| (0..d).map(move |bit| (i, i ^ (1 << bit))).filter(|(a, b)| a < b))
+        .collect()
+}
+
+/// Complete `d`-ary tree of the given depth (at least 1). The root is node `0` and the children of
+/// node `i` are nodes `d * i + 1` to `d * i + d`.
+pub fn dary_tree(d: u32, depth: u32) -> EdgeSet {
+    assert!(d >= 1 && depth >= 1, "a tree needs at least 2 nodes");
+    let node_count: u32 = (0..=depth).map(|level| d.pow(level)).sum();
+    (1..node_count).map(|i| ((i - 1) / d, i)).collect()
+}
+
+/// Number of pairings tried by `random_regular` before falling back to edge switching
+const PAIRING_ATTEMPTS: u32 = 100;
+
+/// Uniformly random `d`-regular graph with `n` nodes generated using the configuration model.
+/// Pairings that result in self-loops or parallel edges are rejected and retried. Dense graphs are
+/// rarely simple in the configuration model, so after `PAIRING_ATTEMPTS` rejected pairings the
+/// graph is instead generated by randomly switching the edges of a circulant graph, which is only
+/// approximately uniform.
+pub fn random_regular(n: u32, d: u32, seed: u64) -> EdgeSet {
+    assert!(d >= 1 && d < n, "degree must be between 1 and n - 1");
+    assert!((n * d).is_multiple_of(2), "n * d must be even");
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    'retry: for _ in 0..PAIRING_ATTEMPTS {
+        // Each node gets `d` half-edges (stubs) that are paired up randomly
+        let mut stubs: Vec<_> = (0..n).flat_map(|i| (0..d).map(move |_| i)).collect();
+        stubs.shuffle(&mut rng);
+
+        let mut uniq = HashSet::new();
+        let mut edges = Vec::with_capacity(stubs.len() / 2);
+        for pair in stubs.chunks(2) {
+            let (a, b) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            if a == b || !uniq.insert((a, b)) {
+                continue 'retry;
+            }
+
+            edges.push((a, b));
+        }
+
+        return edges;
+    }
+
+    // Circulant graph connecting every node to the d / 2 following ones, and to the opposite one
+    // if d is odd (and thus n is even)
+    let mut edges: EdgeSet = (0..n)
+        .flat_map(|i| (1..=d / 2).map(move |k| (i, (i + k) % n)))
+        .chain((0..n / 2).filter(|_| d % 2 == 1).map(|i| (i, i + n / 2)))
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+
+    // Replace random pairs of edges a - b and c - e by a - c and b - e (or a - e and b - c) unless
+    // that results in a self-loop or parallel edges
+    let mut uniq: HashSet<_> = edges.iter().copied().collect();
+    for _ in 0..10 * edges.len() {
+        let (i, j) = (rng.gen_range(0..edges.len()), rng.gen_range(0..edges.len()));
+        let ((a, b), (c, e)) = (edges[i], edges[j]);
+        let (c, e) = if rng.gen_bool(0.5) { (c, e) } else { (e, c) };
+
+        let (x, y) = ((a.min(c), a.max(c)), (b.min(e), b.max(e)));
+        if a == c || b == e || x == y || uniq.contains(&x) || uniq.contains(&y) {
+            continue;
+        }
+
+        uniq.remove(&edges[i]);
+        uniq.remove(&edges[j]);
+        uniq.extend([x, y]);
+        (edges[i], edges[j]) = (x, y);
+    }
+
+    edges.shuffle(&mut rng);
+    edges
+}
+
+/// Erdős–Rényi random graph `G(n, p)`: each of the possible edges between `n` nodes is included
+/// independently with probability `p`. Since the simulator infers the node count from the edges,
+/// trailing isolated nodes are not part of the resulting network.
+pub fn gnp(n: u32, p: f64, seed: u64) -> EdgeSet {
+    try_gnp(n, p, seed, false).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Random graph that is bipartite wrt. even/odd nodes: each of the possible edges between an even
+/// and an odd node out of `n` nodes is included independently with probability `p`, see `gnp`
+pub fn gnp_bipartite(n: u32, p: f64, seed: u64) -> EdgeSet {
+    try_gnp(n, p, seed, true).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Generate a network like `gnp`, or `gnp_bipartite` if `bipartite` is set, returning an error
+/// instead of panicking if the parameters are invalid or the generated graph has no edges
+pub(crate) fn try_gnp(n: u32, p: f64, seed: u64, bipartite: bool) -> Result<EdgeSet, String> {
+    if !(0.0..=1.0).contains(&p) {
+        return Err("p must be a probability".into());
+    }
+
+    if n < 2 {
+        return Err("a random graph needs at least 2 nodes".into());
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let edges: EdgeSet = complete(n)
+        .into_iter()
+        .filter(|(a, b)| !bipartite || (a + b) % 2 == 1)
+        .filter(|_| rng.gen_bool(p))
+        .collect();
+
+    match edges.is_empty() {
+        true => Err("generated graph has no edges, try another seed or a larger p".into()),
+        false => Ok(edges),
+    }
+}
@@ -0,0 +1,250 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::time::Duration;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use crate::generators::EdgeSet;
+use crate::network::{self, IdAssignment, PortNumbering};
+use crate::simulator::DaSimulator;
+use crate::types::*;
+use crate::verifier::Problem;
+
+/// Round limit for runs without a round bound, prevents non-terminating algorithms from hanging
+const DEFAULT_ROUND_LIMIT: u32 = 1000;
+
+/// Property-based test harness for distributed algorithms. For every seed and network family, a
+/// random network is generated and a random port numbering and ID assignment out of the allowed
+/// ones is applied. The algorithm is run on the resulting network, and it must stop within the
+/// round bound with a valid solution to the problem. Failing networks are shrunk to a minimal
+/// failing network by removing edges one at a time. For example:
+///
+/// ```
+/// use pn_simulator::algorithms::Mvc3approx;
+/// use pn_simulator::harness::Harness;
+/// use pn_simulator::network::{IdAssignment, PortNumbering};
+///
+/// Harness::new(Mvc3approx::default)
+///     .family("gnp:10,0.3")
+///     .family("random-regular:8,3")
+///     .seeds(0..5)
+///     .port_numberings(&[PortNumbering::Given, PortNumbering::Random])
+///     .id_assignments(&[IdAssignment::Given, IdAssignment::Random])
+///     .round_bound(|_, max_degree| 2 * max_degree as u32 + 1)
+///     .check();
+/// ```
+pub struct Harness<A: DistributedAlgorithm<S, M>, S: State, M: Message> {
+    algorithm: Box<dyn Fn() -> A>,
+    families: Vec<String>,
+    seeds: Range<u64>,
+    ports: Vec<PortNumbering>,
+    ids: Vec<IdAssignment>,
+    problem: Option<Problem>,
+    // Round bound as a function of the node count and the maximum degree of the network
+    round_bound: Option<Box<dyn Fn(usize, usize) -> u32>>,
+    timeout: Duration,
+    types: PhantomData<fn() -> (S, M)>,
+}
+
+/// A failing case found by the harness
+#[derive(Debug)]
+pub struct Failure {
+    /// Generator specification of the network family, see `network::generate`
+    pub family: String,
+    pub seed: u64,
+    pub ports: PortNumbering,
+    pub ids: IdAssignment,
+    /// Failing network after applying the port numbering and ID assignment
+    pub edges: EdgeSet,
+    /// Minimal failing network found by shrinking `edges`, see `Harness::run`
+    pub shrunk: EdgeSet,
+    /// Reason for the shrunk network failing
+    pub reason: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "property failed on {} (seed {}, ports {}, ids {})", self.family, self.seed, self.ports, self.ids)?;
+        writeln!(f, "original network ({} edges): {:?}", self.edges.len(), self.edges)?;
+        writeln!(f, "shrunk network ({} edges): {:?}", self.shrunk.len(), self.shrunk)?;
+        write!(f, "reason: {}", self.reason)
+    }
+}
+
+impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> Harness<A, S, M> {
+    /// Create a harness for the algorithm instances constructed by the given function. By default,
+    /// seeds `0..10` are used with the given port numbering and ID assignment, and the outputs are
+    /// verified against the problem declared by the algorithm, see `DistributedAlgorithm::problem`.
+    pub fn new(algorithm: impl Fn() -> A + 'static) -> Self {
+        Self {
+            algorithm: Box::new(algorithm),
+            families: Vec::new(),
+            seeds: 0..10,
+            ports: vec![PortNumbering::Given],
+            ids: vec![IdAssignment::Given],
+            problem: None,
+            round_bound: None,
+            timeout: Duration::from_secs(5),
+            types: PhantomData,
+        }
+    }
+
+    /// Add a network family given as a generator specification, see `network::generate`
+    pub fn family(mut self, spec: &str) -> Self {
+        self.families.push(spec.into());
+        self
+    }
+
+    /// Set the seeds to test, each seed produces one case per network family
+    pub fn seeds(mut self, seeds: Range<u64>) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Set the port numbering strategies to pick from
+    pub fn port_numberings(mut self, ports: &[PortNumbering]) -> Self {
+        self.ports = ports.to_vec();
+        self
+    }
+
+    /// Set the node ID assignment strategies to pick from
+    pub fn id_assignments(mut self, ids: &[IdAssignment]) -> Self {
+        self.ids = ids.to_vec();
+        self
+    }
+
+    /// Verify the outputs against the given problem instead of the one declared by the algorithm
+    pub fn problem(mut self, problem: Problem) -> Self {
+        self.problem = Some(problem);
+        self
+    }
+
+    /// Require every node to stop within the number of rounds given by the function of the node
+    /// count and the maximum degree of the network
+    pub fn round_bound(mut self, bound: impl Fn(usize, usize) -> u32 + 'static) -> Self {
+        self.round_bound = Some(Box::new(bound));
+        self
+    }
+
+    /// Set the timeout of a single run for deadlock prevention
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run all the cases, returning the number of cases run or the first failure after shrinking.
+    /// The failing network is shrunk by removing edges, renumbering the remaining nodes in order
+    /// whenever the property still fails after doing so.
+    pub fn run(&self) -> Result<usize, Box<Failure>> {
+        assert!(!self.families.is_empty(), "no network families given");
+        assert!(!self.ports.is_empty() && !self.ids.is_empty(), "no strategies given");
+
+        let mut cases = 0;
+        for seed in self.seeds.clone() {
+            // The strategies are picked deterministically from the seed
+            let mut rng = StdRng::seed_from_u64(seed);
+            for family in &self.families {
+                let ports = *self.ports.choose(&mut rng).unwrap();
+                let ids = *self.ids.choose(&mut rng).unwrap();
+                let mut edges = match network::generate(family, seed) {
+                    Ok(edges) => edges,
+                    Err(e) => {
+                        let reason = format!("generating the network failed: {e}");
+                        let (family, edges, shrunk) = (family.clone(), Vec::new(), Vec::new());
+                        return Err(Box::new(Failure { family, seed, ports, ids, edges, shrunk, reason }));
+                    }
+                };
+
+                network::apply_strategies(&mut edges, ports, ids, seed);
+
+                if let Err(reason) = self.check_case(&edges) {
+                    let (shrunk, reason) = self.shrink(edges.clone(), reason);
+                    return Err(Box::new(Failure { family: family.clone(), seed, ports, ids, edges, shrunk, reason }));
+                }
+
+                cases += 1;
+            }
+        }
+
+        Ok(cases)
+    }
+
+    /// Run all the cases like `run`, panicking with a description of the failure if any
+    pub fn check(&self) {
+        if let Err(failure) = self.run() {
+            panic!("{failure}");
+        }
+    }
+
+    /// Run the algorithm on the given network as is, returning the reason if the property fails
+    fn check_case(&self, edges: &[(u32, u32)]) -> Result<(), String> {
+        let node_count = edges.iter().map(|(a, b)| *a.max(b) as usize + 1).max().unwrap_or(0);
+        let mut degrees = vec![0; node_count];
+        edges.iter().for_each(|(a, b)| {
+            degrees[*a as usize] += 1;
+            degrees[*b as usize] += 1;
+        });
+
+        let bound = self.round_bound.as_ref().map(|b| b(node_count, degrees.into_iter().max().unwrap_or(0)));
+        let mut simulator = DaSimulator::builder((self.algorithm)())
+            .network(edges)
+            .timeout(self.timeout)
+            .round_limit(bound.map_or(DEFAULT_ROUND_LIMIT, |b| b + 1))
+            .build()?;
+
+        let result = simulator.run();
+        if !result.success() {
+            return Err(format!("only {} of {} nodes stopped within {} rounds", result.stopped, result.nodes, result.rounds));
+        }
+
+        if let (Some(bound), Some(round)) = (bound, result.max_stopping_round()) {
+            if round > bound {
+                return Err(format!("a node stopped in round {round}, exceeding the bound of {bound} rounds"));
+            }
+        }
+
+        let problem = self.problem.or_else(|| simulator.algorithm().problem());
+        if let Some(Err(violations)) = problem.map(|p| p.verify(&simulator.ports(), &simulator.outputs())) {
+            let violations: Vec<_> = violations.iter().map(|v| v.to_string()).collect();
+            return Err(format!("invalid solution: {}", violations.join(", ")));
+        }
+
+        Ok(())
+    }
+
+    /// Shrink the given failing network by removing edges one at a time as long as the property
+    /// keeps failing. Renumbering the nodes may change the behavior of algorithms that depend on
+    /// the node IDs, so the network is only renumbered if it keeps failing.
+    fn shrink(&self, mut edges: EdgeSet, mut reason: String) -> (EdgeSet, String) {
+        loop {
+            let smaller = (0..edges.len()).filter(|_| edges.len() > 1).find_map(|i| {
+                let mut candidate = edges.clone();
+                candidate.remove(i);
+                [compact(&candidate), candidate]
+                    .into_iter()
+                    .find_map(|c| self.check_case(&c).err().map(|r| (c, r)))
+            });
+
+            match smaller {
+                Some((e, r)) => (edges, reason) = (e, r),
+                None => return (edges, reason),
+            }
+        }
+    }
+}
+
+/// Renumber the nodes of the network consecutively from zero in order, dropping isolated nodes
+fn compact(edges: &[(u32, u32)]) -> EdgeSet {
+    let mut nodes: BTreeMap<u32, u32> = edges.iter().flat_map(|(a, b)| [(*a, 0), (*b, 0)]).collect();
+    nodes.values_mut().enumerate().for_each(|(i, v)| *v = i as u32);
+    edges.iter().map(|(a, b)| (nodes[a], nodes[b])).collect()
+}
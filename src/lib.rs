@@ -0,0 +1,31 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+pub mod adversary;
+pub mod algorithms;
+pub mod builder;
+pub mod experiment;
+pub mod faults;
+pub mod generators;
+pub mod harness;
+pub mod lift;
+pub mod network;
+pub mod observer;
+pub mod optimum;
+pub mod registry;
+pub mod scenario;
+mod types;
+pub mod simulator;
+mod tui;
+pub mod verifier;
+mod visualizer;
+
+// Re-exports of the core API for implementing and simulating algorithms
+pub use types::{DistributedAlgorithm, Input, Inputs, Message, Outbox, Output, State};
+pub use builder::{Jitter, Options, SimulatorBuilder};
+pub use simulator::{DaSimulator, RunResult};
@@ -0,0 +1,144 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashSet;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use crate::generators::EdgeSet;
+use crate::types::State;
+
+/// A lift (covering graph) of a base network together with its covering map. Every base node `v`
+/// is replaced by `k` copies `(v, 0), ..., (v, k - 1)` with indices `v * k + i`, and every base edge
+/// `{u, v}` is replaced by `k` edges `{(u, i), (v, p(i))}` for some permutation `p` of the copies.
+///
+/// The copies are numbered so that they are ordered the same way as their base nodes, and the
+/// lifted edges are emitted in the order of the base edges. This way each copy gets exactly the
+/// same port numbering as its image, which makes the lift indistinguishable from the base network
+/// for any algorithm in the PN model.
+///
+/// The base network may be a multigraph (i.e. contain parallel edges), but self-loops are not
+/// supported since their port numbering cannot be expressed in the edge set format.
+pub struct Lift {
+    edges: EdgeSet,
+    map: Vec<u32>,
+    k: u32,
+}
+
+impl Lift {
+    /// Construct a `k`-lift of the given base network. `permutations` contains a permutation of
+    /// `0..k` for each base edge in order, where the base edge `{u, v}` with `u < v` is lifted to
+    /// the edges `{(u, i), (v, permutations[e][i])}`.
+    pub fn new(base: &[(u32, u32)], k: u32, permutations: &[Vec<u32>]) -> Self {
+        assert!(k >= 1, "a lift needs at least one copy of the base network");
+        assert_eq!(base.len(), permutations.len(), "each base edge needs a permutation");
+        assert!(base.iter().all(|(a, b)| a != b), "self-loops are not supported");
+
+        let node_count = 1 + base
+            .iter()
+            .map(|(a, b)| a.max(b))
+            .max()
+            .expect("no edges given");
+
+        let edges = base
+            .iter()
+            .zip(permutations)
+            .flat_map(|(&(a, b), p)| {
+                let mut sorted = p.clone();
+                sorted.sort_unstable();
+                assert!(sorted.into_iter().eq(0..k), "invalid permutation: {p:?}");
+
+                let (u, v) = (a.min(b), a.max(b));
+                (0..k).map(move |i| (u * k + i, v * k + p[i as usize]))
+            })
+            .collect();
+
+        Self {
+            edges,
+            map: (0..node_count * k).map(|i| i / k).collect(),
+            k,
+        }
+    }
+
+    /// Construct a random `k`-lift of the given base network from the given seed. For multigraph
+    /// bases, lifts that contain parallel edges are rejected so that the result can be simulated.
+    pub fn random(base: &[(u32, u32)], k: u32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for _ in 0..1000 {
+            let permutations: Vec<_> = base
+                .iter()
+                .map(|_| {
+                    let mut p: Vec<_> = (0..k).collect();
+                    p.shuffle(&mut rng);
+                    p
+                })
+                .collect();
+
+            let lift = Self::new(base, k, &permutations);
+            if lift.is_simple() {
+                return lift;
+            }
+        }
+
+        panic!("unable to find a simple {k}-lift, try a larger k");
+    }
+
+    /// Construct the bipartite double cover of the given base network. This is the 2-lift where
+    /// every edge crosses between the two copies, so the result is always bipartite.
+    pub fn bipartite_double_cover(base: &[(u32, u32)]) -> Self {
+        Self::new(base, 2, &vec![vec![1, 0]; base.len()])
+    }
+
+    /// The edge set of the lift, which can be passed to `DaSimulator::from_network`
+    pub fn edges(&self) -> &[(u32, u32)] {
+        &self.edges
+    }
+
+    /// The number of copies of each base node in the lift
+    pub fn copies(&self) -> u32 {
+        self.k
+    }
+
+    /// The covering map, i.e. the image of each lift node in the base network
+    pub fn covering_map(&self) -> &[u32] {
+        &self.map
+    }
+
+    /// Determine if the lift is a simple graph, which is required for simulating it
+    pub fn is_simple(&self) -> bool {
+        let mut uniq = HashSet::new();
+        self.edges.iter().all(|e| uniq.insert(*e))
+    }
+
+    /// Check that every node of the lift ended up with the same local output as its image in the
+    /// base network, see `State::output`. The states may still differ in their internals. Returns
+    /// the lift nodes whose output differs from the output of their image.
+    pub fn verify<S: State>(&self, base: &[S], lifted: &[S]) -> Result<(), Vec<u32>> {
+        assert_eq!(base.len() as u32 * self.k, lifted.len() as u32, "node count mismatch");
+        Self::mismatches(lifted.iter().enumerate().filter(|(i, s)| s.output() != base[self.map[*i] as usize].output()))
+    }
+
+    /// Check that all nodes of the lift with the same image (i.e. in the same fiber) ended up with
+    /// the same local output. This requires no simulation of the base network, so it also works
+    /// for multigraph bases. Returns the lift nodes whose output differs from the output of the
+    /// first node of their fiber.
+    pub fn verify_fibers<S: State>(&self, lifted: &[S]) -> Result<(), Vec<u32>> {
+        assert_eq!(self.map.len(), lifted.len(), "node count mismatch");
+        let k = self.k as usize;
+        Self::mismatches(lifted.iter().enumerate().filter(|(i, s)| s.output() != lifted[i - i % k].output()))
+    }
+
+    /// Helper for collecting the indices of mismatching nodes into a result
+    fn mismatches<'a, S: 'a>(iter: impl Iterator<Item=(usize, &'a S)>) -> Result<(), Vec<u32>> {
+        let nodes: Vec<_> = iter.map(|(i, _)| i as u32).collect();
+        match nodes.is_empty() {
+            true => Ok(()),
+            false => Err(nodes),
+        }
+    }
+}
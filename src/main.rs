@@ -6,67 +6,469 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-#![feature(type_alias_impl_trait)]
-
-mod algorithms;
-mod types;
-mod simulator;
-
-use types::*;
-use std::time::{Duration};
-use simulator::DaSimulator;
-
-/// The main function. Take a look at the edge set format in the given examples to define your own
-/// network, then select it together with the algorithm of your choice below. Run your simulation
-/// with `cargo run --release`.
-fn main() {
-    // Edge sets for some generic networks
-    let _network1 = [
-        (0, 2), (0, 1), (0, 3),
-        (1, 2), (1, 3), (2, 3),
-    ];
-
-    let _network2 = [
-        (0, 1), (0, 2), (1, 3), (2, 3), (2, 4), (3, 4),
-        (1, 5), (4, 5), (4, 6), (5, 6), (6, 7), (6, 8)
-    ];
-
-    let _network3 = [
-        (0, 1), (0, 2), (1, 3), (2, 3), (2, 4), (3, 4),
-        (1, 5), (4, 5), (4, 6), (5, 7), (6, 7)
-    ];
-
-    let _triangle = [
-        (0, 1), (1, 2), (0, 2)
-    ];
-
-    // Networks that are bipartite wrt. even/odd nodes
-    let _bp_network1 = [
-        (0, 1), (2, 1), (4, 1), (3, 2), (5, 2)
-    ];
-
-    let _bp_network2 = [
-        (0, 1), (1, 2), (1, 4), (2, 3), (2, 5)
-    ];
-
-    let _square = [
-        (0, 1), (1, 2), (2, 3), (0, 3)
-    ];
-
-    // A star network
-    let _star_network: Vec<_> = (0..10).map(|i| (0, i + 1)).collect();
-
-    // Select your algorithm here
-    // type Algorithm = algorithms::IsomorphicNeighborhood<5>;
-    // type Algorithm = algorithms::BipartiteMaximalMatching;
-    type Algorithm = algorithms::Mvc3approx;
-
-    // Select your network here
-    let network = &_network2;
-
-    let mut simulator: DaSimulator<Algorithm, _, _> =
-        DaSimulator::from_network(network, Duration::from_secs(5));
-
-    simulator.run(0);
-    simulator.print();
+use std::fmt::Display;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use pn_simulator::{experiment, network, registry, Options, RunResult};
+use pn_simulator::experiment::{Experiment, Source};
+use pn_simulator::faults::{Crashes, MessageFaults};
+use pn_simulator::network::{IdAssignment, PortNumbering};
+use pn_simulator::registry::{Params, Simulation};
+use pn_simulator::scenario::Scenario;
+
+/// Maximum artificial delay of the nodes before sending and receiving with `--check-scheduling`
+const SCHEDULING_JITTER: Duration = Duration::from_millis(1);
+
+/// Simulate distributed algorithms of the PN, LOCAL and CONGEST models on arbitrary networks. The
+/// exit code is 0 if every node reached a stopping state with a valid solution, 1 if some did not or
+/// the solution is invalid or depends on the thread scheduling, and 2 on errors.
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("source").args(["network", "generator"])))]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Algorithm to simulate, see --list for the available ones
+    #[arg(short, long, required_unless_present = "list")]
+    algorithm: Option<String>,
+
+    /// Parameter for the algorithm given as name=value, can be repeated
+    #[arg(short, long = "param", value_parser = parse_param)]
+    params: Vec<(String, String)>,
+
+    /// List the available algorithms and their parameters
+    #[arg(long, exclusive = true)]
+    list: bool,
+
+    /// File to read the network from, one edge per line given as two node indices
+    #[arg(short, long, required_unless_present_any = ["generator", "list"])]
+    network: Option<PathBuf>,
+
+    /// Generator for the network, e.g. cycle:1000, grid:10,10, gnp:100,0.05 or example:network2
+    #[arg(short, long)]
+    generator: Option<String>,
+
+    /// Timeout in seconds for deadlock prevention
+    #[arg(short, long, default_value = "5", value_parser = parse_timeout)]
+    timeout: Duration,
+
+    /// Communication round limit, runs until natural termination if not given
+    #[arg(short, long)]
+    round_limit: Option<u32>,
+
+    /// Port numbering strategy: given (edge order) or random
+    #[arg(long, default_value = "given")]
+    ports: PortNumbering,
+
+    /// Node ID assignment strategy: given (edge indices) or random
+    #[arg(long, default_value = "given")]
+    ids: IdAssignment,
+
+    /// Seed for the random generators and strategies
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+
+    /// Output format for the resulting network
+    #[arg(short, long, value_enum, default_value_t = Format::Dot)]
+    format: Format,
+
+    /// Show a live terminal UI while simulating
+    #[arg(long)]
+    tui: bool,
+
+    /// Make the given node crash at the beginning of the given round, given as <node>:<round>, can
+    /// be repeated. Crashed nodes send nothing and never receive again.
+    #[arg(long = "crash", value_name = "NODE:ROUND", value_parser = parse_pair::<usize, u32>, conflicts_with = "random_crashes")]
+    crashes: Vec<(usize, u32)>,
+
+    /// Make the given number of random nodes crash in random rounds up to the given one, given as
+    /// <count>:<max round>. The crashes are drawn using the seed.
+    #[arg(long, value_name = "COUNT:MAX_ROUND", value_parser = parse_pair::<usize, u32>)]
+    random_crashes: Option<(usize, u32)>,
+
+    /// Probability of dropping each message, drawn using the seed
+    #[arg(long, value_name = "P", default_value_t = 0.0)]
+    drop: f64,
+
+    /// Probability of duplicating each message, i.e. delivering it again in the next round in
+    /// place of the next message over the same edge, drawn using the seed
+    #[arg(long, value_name = "P", default_value_t = 0.0)]
+    duplicate: f64,
+
+    /// Restrict the dropped and duplicated messages to the given edge, given as <node>:<node>, can
+    /// be repeated
+    #[arg(long = "faulty-edge", value_name = "NODE:NODE", value_parser = parse_pair::<usize, usize>)]
+    faulty_edges: Vec<(usize, usize)>,
+
+    /// Make the given node Byzantine, can be repeated. Byzantine nodes send nothing at all, but
+    /// are not required to stop.
+    #[arg(long, value_name = "NODE")]
+    byzantine: Vec<usize>,
+
+    /// Run the simulation the given number of times with randomized thread scheduling and fail if
+    /// the final states, stopping rounds or totals such as the number of rounds differ between the
+    /// runs
+    #[arg(long, value_name = "RUNS", value_parser = clap::value_parser!(u32).range(2..), conflicts_with = "tui")]
+    check_scheduling: Option<u32>,
+
+    /// Write an offline HTML visualization of the execution into the given file
+    #[arg(long)]
+    html: Option<PathBuf>,
+
+    /// Write one DOT frame per communication round into the given directory
+    #[arg(long)]
+    frames: Option<PathBuf>,
+
+    /// Print a histogram of the rounds in which the nodes stopped to stderr
+    #[arg(long)]
+    histogram: bool,
+}
+
+/// Subcommands of the simulator
+#[derive(Subcommand)]
+enum Command {
+    /// Run every algorithm on every network for every combination of seed, port numbering and ID
+    /// assignment, and collect the results into a single table
+    Batch(BatchArgs),
+    /// Run the simulations described by the given TOML scenario files and check their expected
+    /// outcomes, see the `scenarios` directory for examples
+    Scenario(ScenarioArgs),
+}
+
+/// Arguments of the `scenario` subcommand
+#[derive(clap::Args)]
+struct ScenarioArgs {
+    /// Scenario files to run
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Output format for the resulting networks
+    #[arg(short, long, value_enum, default_value_t = Format::Summary)]
+    format: Format,
+}
+
+/// Arguments of the `batch` subcommand
+#[derive(clap::Args)]
+#[command(group(ArgGroup::new("source").args(["network", "generator"]).multiple(true).required(true)))]
+struct BatchArgs {
+    /// Algorithm to simulate given as name or name:param=value,..., can be repeated
+    #[arg(short, long, required = true)]
+    algorithm: Vec<String>,
+
+    /// File to read a network from, can be repeated
+    #[arg(short, long)]
+    network: Vec<PathBuf>,
+
+    /// Generator for a network, can be repeated
+    #[arg(short, long)]
+    generator: Vec<String>,
+
+    /// Seeds given as a comma-separated list of seeds and ranges, e.g. 0..10,42
+    #[arg(short, long, default_value = "0", value_delimiter = ',', value_parser = parse_seeds)]
+    seeds: Vec<Range<u64>>,
+
+    /// Port numbering strategies given as a comma-separated list
+    #[arg(long, default_value = "given", value_delimiter = ',')]
+    ports: Vec<PortNumbering>,
+
+    /// Node ID assignment strategies given as a comma-separated list
+    #[arg(long, default_value = "given", value_delimiter = ',')]
+    ids: Vec<IdAssignment>,
+
+    /// Timeout in seconds for deadlock prevention in every simulation
+    #[arg(short, long, default_value = "5", value_parser = parse_timeout)]
+    timeout: Duration,
+
+    /// Communication round limit for every simulation, runs until natural termination if not given
+    #[arg(short, long)]
+    round_limit: Option<u32>,
+
+    /// Output format for the result table
+    #[arg(short, long, value_enum, default_value_t = TableFormat::Markdown)]
+    format: TableFormat,
+
+    /// Write the result table into the given file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+/// Output formats for the result table of a batch
+#[derive(Clone, Copy, ValueEnum)]
+enum TableFormat {
+    Csv,
+    Markdown,
+}
+
+/// Output formats for the resulting network
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Graphviz DOT with node IDs and states
+    Dot,
+    /// JSON with the nodes, their states and outputs, and the edges
+    Json,
+    /// Short textual summary of the run
+    Summary,
+}
+
+/// The main function. Parses the command line, builds the network and runs the selected algorithm
+/// on it, see `--help` for the options.
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if args.list {
+        list_algorithms();
+        return ExitCode::SUCCESS;
+    }
+
+    let result = match &args.command {
+        Some(Command::Batch(batch_args)) => batch(batch_args),
+        Some(Command::Scenario(scenario_args)) => scenarios(scenario_args),
+        None => run(&args),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Build the network and dispatch the simulation to the selected algorithm. Returns whether every
+/// node reached a stopping state with a valid solution.
+fn run(args: &Args) -> Result<bool, String> {
+    let edges = match (&args.network, &args.generator) {
+        (Some(path), _) => network::read_edges(path).map_err(|e| format!("{}: {e}", path.display()))?,
+        (_, Some(spec)) => network::generate(spec, args.seed)?,
+        _ => unreachable!("network source is required"),
+    };
+
+    let options = Options {
+        timeout: args.timeout,
+        round_limit: args.round_limit,
+        ports: args.ports,
+        ids: args.ids,
+        seed: args.seed,
+        // The trace is only needed for visualizing the execution
+        trace: args.html.is_some() || args.frames.is_some(),
+        crashes: match args.random_crashes {
+            Some((count, max_round)) => Crashes::Random { count, max_round, seed: args.seed },
+            None if !args.crashes.is_empty() => Crashes::Schedule(args.crashes.clone()),
+            None => Crashes::None,
+        },
+        message_faults: MessageFaults {
+            drop: args.drop,
+            duplicate: args.duplicate,
+            edges: args.faulty_edges.clone(),
+            seed: args.seed,
+            ..MessageFaults::default()
+        },
+        byzantine: args.byzantine.clone(),
+        ..Options::default()
+    };
+
+    let name = args.algorithm.as_deref().expect("algorithm is required");
+    let entry = registry::lookup(name).ok_or_else(|| format!("unknown algorithm: {name}, see --list"))?;
+    let params: Params = args.params.iter().cloned().collect();
+    let simulator = entry.build(&edges, &options, &params)?;
+
+    simulate(args, simulator, edges.len())
+}
+
+/// Run the given simulation and write the requested outputs
+fn simulate(args: &Args, mut simulator: Box<dyn Simulation>, edge_count: usize) -> Result<bool, String> {
+    let result = match (args.tui, args.check_scheduling) {
+        (true, _) => simulator.run_tui().map_err(|e| format!("terminal: {e}"))?,
+        (false, Some(runs)) => match simulator.check_scheduling(runs, SCHEDULING_JITTER) {
+            Ok(result) => result,
+            Err(divergence) => {
+                eprint!("{divergence}");
+                return Ok(false);
+            }
+        },
+        (false, None) => simulator.run(),
+    };
+
+    if let Some(path) = &args.html {
+        simulator.write_html(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+
+    if let Some(dir) = &args.frames {
+        simulator.write_frames(dir, true).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+
+    if args.histogram {
+        print_histogram(&result);
+    }
+
+    print_output(args.format, simulator.as_ref(), &result, edge_count);
+    Ok(result.success() && !matches!(simulator.verify().filter(|_| result.complete()), Some(Err(_))))
+}
+
+/// Print the resulting network of a simulation in the given format
+fn print_output(format: Format, simulator: &dyn Simulation, result: &RunResult, edge_count: usize) {
+    match format {
+        Format::Dot => simulator.print(true),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&simulator.to_json()).unwrap()),
+        Format::Summary => {
+            println!("Algorithm: {}", simulator.algorithm_name());
+            println!("Nodes: {}, edges: {}", result.nodes, edge_count);
+            println!("Rounds: {}", result.rounds);
+            println!("Stopped: {}/{}", result.stopped, result.nodes);
+            if result.crashed() > 0 {
+                println!("Crashed: {}/{}", result.crashed(), result.nodes);
+                result.crashes.iter().enumerate().filter_map(|(v, c)| c.map(|c| (v, c)))
+                    .for_each(|(v, c)| println!("  node {v} in round {c}"));
+            }
+            if result.byzantine_nodes() > 0 {
+                let nodes: Vec<_> = result.byzantine.iter().enumerate().filter(|(_, b)| **b).map(|(v, _)| v.to_string()).collect();
+                println!("Byzantine: {}", nodes.join(", "));
+            }
+            if result.faults.total() > 0 {
+                let f = result.faults;
+                println!("Faulty messages: {} dropped, {} duplicated, {} corrupted", f.dropped, f.duplicated, f.corrupted);
+            }
+            if let Some(p) = &result.panic {
+                println!("Panic: {p}");
+            }
+            if let (Some(max), Some(avg)) = (result.max_stopping_round(), result.average_stopping_round()) {
+                println!("Stopping round: max {max}, average {avg:.2}");
+            }
+            println!("Messages: {} ({} no-op), {:.2} per edge excluding no-ops",
+                     result.messages, result.noops, result.messages_per_edge());
+            if let Some(bits) = result.bits {
+                println!("Bits: {bits}");
+            }
+            if let Some(size) = simulator.solution_size() {
+                println!("Solution size: {size}");
+            }
+            // Nodes that did not stop have no output, so only complete solutions are verified
+            if let Some(Err(violations)) = simulator.verify().filter(|_| result.complete()) {
+                println!("Invalid solution: {} violation(s)", violations.len());
+                violations.iter().for_each(|v| println!("  {v}"));
+            }
+            if let Some(a) = simulator.approximation() {
+                // The objective may count e.g. matched edges instead of the nodes in the solution
+                println!("Objective value: {}, optimum: {}, approximation ratio: {:.3}", a.value, a.optimum, a.ratio());
+            }
+            println!("Result: {}", if result.success() { "success" } else { "FAILED" });
+        }
+    }
+}
+
+/// Print a histogram of the stopping rounds of the nodes to stderr, scaled to fit the terminal
+fn print_histogram(result: &RunResult) {
+    let histogram = result.stopping_histogram();
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1);
+    let width = histogram.len().to_string().len();
+
+    eprintln!("\nStopping rounds:");
+    for (round, count) in histogram.iter().enumerate().skip(1) {
+        eprintln!("{round:>width$} | {:<50} {count}", "#".repeat((count * 50).div_ceil(max)));
+    }
+}
+
+/// Run the given scenario files one after another. Returns whether every scenario met its
+/// expected outcome.
+fn scenarios(args: &ScenarioArgs) -> Result<bool, String> {
+    let mut passed = 0;
+    for path in &args.files {
+        let scenario = Scenario::load(path)?;
+        let outcome = scenario.run().map_err(|e| format!("{}: {e}", path.display()))?;
+
+        println!("\n=== {}: {}", path.display(), scenario.description);
+        print_output(args.format, outcome.simulation.as_ref(), &outcome.result, outcome.edges);
+
+        match outcome.failures.is_empty() {
+            true => passed += 1,
+            false => outcome.failures.iter().for_each(|f| println!("FAILED: {f}")),
+        }
+    }
+
+    println!("\n{passed}/{} scenario(s) passed", args.files.len());
+    Ok(passed == args.files.len())
+}
+
+/// Run a batch of simulations and write the result table. Returns whether every node reached a
+/// stopping state in every simulation.
+fn batch(args: &BatchArgs) -> Result<bool, String> {
+    let experiment = Experiment {
+        algorithms: args.algorithm.clone(),
+        networks: args.network
+            .iter()
+            .map(|p| Source::File(p.clone()))
+            .chain(args.generator.iter().map(|g| Source::Generator(g.clone())))
+            .collect(),
+        seeds: args.seeds.iter().cloned().flatten().collect(),
+        ports: args.ports.clone(),
+        ids: args.ids.clone(),
+        timeout: args.timeout,
+        round_limit: args.round_limit,
+    };
+
+    let rows = experiment.run()?;
+    for (algorithm, row) in experiment::worst_ratios(&rows) {
+        eprintln!("Worst approximation ratio of {algorithm}: {:.3} on {} (seed {}, ports {}, ids {})",
+                  row.approximation.unwrap().ratio(), row.network, row.seed, row.ports, row.ids);
+    }
+
+    let mut table = Vec::new();
+    match args.format {
+        TableFormat::Csv => experiment::write_csv(&mut table, &rows),
+        TableFormat::Markdown => experiment::write_markdown(&mut table, &rows),
+    }.expect("writing into memory failed");
+
+    match &args.output {
+        Some(path) => std::fs::write(path, table).map_err(|e| format!("{}: {e}", path.display()))?,
+        None => print!("{}", String::from_utf8(table).unwrap()),
+    }
+
+    Ok(rows.iter().all(|r| r.status == experiment::Status::Success))
+}
+
+/// Parse a single seed or a (half-open) range of seeds such as `0..10`
+fn parse_seeds(s: &str) -> Result<Range<u64>, String> {
+    let int = |i: &str| i.trim().parse::<u64>().map_err(|e| format!("invalid seed {i}: {e}"));
+    match s.split_once("..") {
+        Some((a, b)) => Ok(int(a)?..int(b)?),
+        None => int(s).map(|i| i..i + 1),
+    }
+}
+
+/// Parse a timeout given in (possibly fractional) seconds
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let seconds = s.trim().parse::<f64>().map_err(|e| format!("invalid number {s}: {e}"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid timeout {s}: {e}"))
+}
+
+/// Parse a pair of values of the form `a:b`, such as a crash given as `<node>:<round>`
+fn parse_pair<A: FromStr, B: FromStr>(s: &str) -> Result<(A, B), String> where A::Err: Display, B::Err: Display {
+    let (a, b) = s.split_once(':').ok_or_else(|| format!("expected <a>:<b>, got {s}"))?;
+    let a = a.trim().parse().map_err(|e| format!("invalid number {a}: {e}"))?;
+    let b = b.trim().parse().map_err(|e| format!("invalid number {b}: {e}"))?;
+    Ok((a, b))
+}
+
+/// Parse an algorithm parameter of the form `name=value`
+fn parse_param(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .ok_or_else(|| format!("expected name=value, got {s}"))
+}
+
+/// Print the algorithms available in the registry together with their parameters
+fn list_algorithms() {
+    for entry in registry::algorithms() {
+        println!("{}\n    {}", entry.name, entry.description);
+        for p in entry.parameters {
+            println!("    -p {}=<value>  {} (default: {})", p.name, p.description, p.default);
+        }
+    }
 }
@@ -0,0 +1,260 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use crate::generators::{self, EdgeSet};
+
+/// Strategy for assigning port numbers to the edges of a network
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PortNumbering {
+    /// Port numbers follow the order of the given edges
+    Given,
+    /// The order of the edges is shuffled before building the network
+    Random,
+}
+
+/// Strategy for assigning node identifiers (indices) to the nodes of a network
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdAssignment {
+    /// Nodes keep the identifiers they have in the given edges
+    Given,
+    /// Nodes are relabeled using a random permutation
+    Random,
+}
+
+impl FromStr for PortNumbering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "given" => Ok(PortNumbering::Given),
+            "random" => Ok(PortNumbering::Random),
+            _ => Err(format!("unknown port numbering: {s} (expected given or random)")),
+        }
+    }
+}
+
+impl FromStr for IdAssignment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "given" => Ok(IdAssignment::Given),
+            "random" => Ok(IdAssignment::Random),
+            _ => Err(format!("unknown ID assignment: {s} (expected given or random)")),
+        }
+    }
+}
+
+impl fmt::Display for PortNumbering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortNumbering::Given => write!(f, "given"),
+            PortNumbering::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl fmt::Display for IdAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdAssignment::Given => write!(f, "given"),
+            IdAssignment::Random => write!(f, "random"),
+        }
+    }
+}
+
+/// Apply the given port numbering and ID assignment strategies to the edge set, using the given
+/// seed for the random ones. Note that the simulator always numbers the ports leading to
+/// lower-indexed neighbors first, so the port numbering is only randomized within that constraint
+/// unless the IDs are randomized as well.
+pub fn apply_strategies(edges: &mut EdgeSet, ports: PortNumbering, ids: IdAssignment, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    if ids == IdAssignment::Random {
+        let node_count = 1 + edges.iter().map(|(a, b)| *a.max(b)).max().unwrap_or(0);
+        let mut permutation: Vec<_> = (0..node_count).collect();
+        permutation.shuffle(&mut rng);
+        edges.iter_mut().for_each(|(a, b)| {
+            *a = permutation[*a as usize];
+            *b = permutation[*b as usize];
+        });
+    }
+
+    if ports == PortNumbering::Random {
+        edges.shuffle(&mut rng);
+    }
+}
+
+/// Read an edge set from a file. Each non-empty line contains a single edge given as two node
+/// indices separated by whitespace or a comma, and lines starting with `#` are ignored.
+pub fn read_edges(path: impl AsRef<Path>) -> io::Result<EdgeSet> {
+    let invalid = |line, msg| io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"));
+
+    fs::read_to_string(path)?
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(i, l)| {
+            let nodes = l
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<u32>().map_err(|e| invalid(i, format!("{s}: {e}"))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match nodes[..] {
+                [a, b] => Ok((a, b)),
+                _ => Err(invalid(i, format!("expected two node indices, got {}", nodes.len()))),
+            }
+        })
+        .collect()
+}
+
+/// Build a network from a generator specification of the form `<family>:<parameters>`, where the
+/// parameters are separated by commas. The random families use the given seed. Supported:
+///
+/// - `path:<n>`, `cycle:<n>`, `star:<leaves>`, `complete:<n>`, `complete-bipartite:<a>,<b>`
+/// - `grid:<width>,<height>`, `torus:<width>,<height>`, `hypercube:<d>`, `tree:<d>,<depth>`
+/// - `random-regular:<n>,<d>`, `gnp:<n>,<p>`, `gnp-bipartite:<n>,<p>`
+/// - `example:<name>` for the example networks, see `example`
+///
+/// Unlike the generators themselves, invalid parameters are reported as errors.
+pub fn generate(spec: &str, seed: u64) -> Result<EdgeSet, String> {
+    let (family, params) = spec.split_once(':').unwrap_or((spec, ""));
+    if family == "example" {
+        return example(params).ok_or_else(|| format!("unknown example network: {params}"));
+    }
+
+    let params: Vec<_> = params.split(',').filter(|p| !p.is_empty()).collect();
+    let int = |i: usize| -> Result<u32, String> {
+        params
+            .get(i)
+            .ok_or_else(|| format!("missing parameter {} for {family}", i + 1))?
+            .parse()
+            .map_err(|e| format!("invalid parameter {} for {family}: {e}", i + 1))
+    };
+
+    // The generators panic on invalid parameters, so they are checked beforehand
+    let require = |valid: bool, reason: &str| match valid {
+        true => Ok(()),
+        false => Err(format!("invalid parameters for {family}: {reason}")),
+    };
+
+    Ok(match family {
+        "path" => {
+            let n = int(0)?;
+            require(n >= 2, "a path needs at least 2 nodes")?;
+            generators::path(n)
+        }
+        "cycle" => {
+            let n = int(0)?;
+            require(n >= 3, "a simple cycle needs at least 3 nodes")?;
+            generators::cycle(n)
+        }
+        "star" => {
+            let leaves = int(0)?;
+            require(leaves >= 1, "a star needs at least 1 leaf")?;
+            generators::star(leaves)
+        }
+        "complete" => {
+            let n = int(0)?;
+            require(n >= 2, "a complete graph needs at least 2 nodes")?;
+            generators::complete(n)
+        }
+        "complete-bipartite" => {
+            let (a, b) = (int(0)?, int(1)?);
+            require(a >= 1 && b >= 1, "both partitions need at least 1 node")?;
+            require(a.checked_add(b).is_some(), "too many nodes")?;
+            generators::complete_bipartite(a, b)
+        }
+        "grid" | "torus" => {
+            let (width, height) = (int(0)?, int(1)?);
+            require(width.checked_mul(height).is_some(), "too many nodes")?;
+            match family {
+                "grid" => {
+                    require(width * height >= 2, "a grid needs at least 2 nodes")?;
+                    generators::grid(width, height)
+                }
+                _ => {
+                    require(width >= 3 && height >= 3, "torus dimensions must be at least 3")?;
+                    generators::torus(width, height)
+                }
+            }
+        }
+        "hypercube" => {
+            let d = int(0)?;
+            require((1..32).contains(&d), "hypercube dimension must be between 1 and 31")?;
+            generators::hypercube(d)
+        }
+        "tree" => {
+            let (d, depth) = (int(0)?, int(1)?);
+            require(d >= 1 && depth >= 1, "a tree needs at least 2 nodes")?;
+            let nodes = (0..=depth).try_fold(0u32, |n, level| d.checked_pow(level).and_then(|l| n.checked_add(l)));
+            require(nodes.is_some(), "too many nodes")?;
+            generators::dary_tree(d, depth)
+        }
+        "random-regular" => {
+            let (n, d) = (int(0)?, int(1)?);
+            require(d >= 1 && d < n, "degree must be between 1 and n - 1")?;
+            require(n.checked_mul(d).is_some_and(|s| s % 2 == 0), "n * d must be even")?;
+            generators::random_regular(n, d, seed)
+        }
+        "gnp" | "gnp-bipartite" => {
+            let p = params
+                .get(1)
+                .ok_or_else(|| format!("missing parameter 2 for {family}"))?
+                .parse()
+                .map_err(|e| format!("invalid parameter 2 for {family}: {e}"))?;
+            generators::try_gnp(int(0)?, p, seed, family == "gnp-bipartite")
+                .map_err(|e| format!("invalid parameters for {family}: {e}"))?
+        }
+        _ => return Err(format!("unknown network family: {family}")),
+    })
+}
+
+/// Retrieve one of the example networks by name: `network1`, `network2`, `network3` and `triangle`
+/// are generic networks, while `bp-network1`, `bp-network2` and `square` are bipartite wrt.
+/// even/odd nodes.
+pub fn example(name: &str) -> Option<EdgeSet> {
+    let edges: &[(u32, u32)] = match name {
+        "network1" => &[
+            (0, 2), (0, 1), (0, 3),
+            (1, 2), (1, 3), (2, 3),
+        ],
+        "network2" => &[
+            (0, 1), (0, 2), (1, 3), (2, 3), (2, 4), (3, 4),
+            (1, 5), (4, 5), (4, 6), (5, 6), (6, 7), (6, 8)
+        ],
+        "network3" => &[
+            (0, 1), (0, 2), (1, 3), (2, 3), (2, 4), (3, 4),
+            (1, 5), (4, 5), (4, 6), (5, 7), (6, 7)
+        ],
+        "triangle" => &[
+            (0, 1), (1, 2), (0, 2)
+        ],
+        "bp-network1" => &[
+            (0, 1), (2, 1), (4, 1), (3, 2), (5, 2)
+        ],
+        "bp-network2" => &[
+            (0, 1), (1, 2), (1, 4), (2, 3), (2, 5)
+        ],
+        "square" => &[
+            (0, 1), (1, 2), (2, 3), (0, 3)
+        ],
+        _ => return None,
+    };
+
+    Some(edges.to_vec())
+}
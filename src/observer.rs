@@ -0,0 +1,78 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::faults::MessageFault;
+use crate::simulator::RunResult;
+use crate::types::*;
+
+/// Hook for instrumenting a simulation, e.g. for collecting statistics, tracing or checking
+/// assertions. Observers are registered using `SimulatorBuilder::observer`. Every node runs in its
+/// own thread, so the callbacks are invoked concurrently from the node threads and the observer
+/// must use interior mutability for keeping track of anything. Communication rounds are numbered
+/// starting from 1, and ports starting from 0. All callbacks do nothing by default. For example:
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use pn_simulator::{DaSimulator, Message, State};
+/// use pn_simulator::algorithms::Mvc3approx;
+/// use pn_simulator::generators;
+/// use pn_simulator::observer::Observer;
+///
+/// #[derive(Default)]
+/// struct OutputCounter(AtomicU32);
+///
+/// impl<S: State, M: Message> Observer<S, M> for OutputCounter {
+///     fn output_reached(&self, _node: usize, _round: u32, _state: &S) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Arc::new(OutputCounter::default());
+/// let mut simulator = DaSimulator::builder(Mvc3approx::default())
+///     .network(&generators::cycle(10))
+///     .observer(counter.clone())
+///     .build()
+///     .unwrap();
+///
+/// simulator.run();
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 10);
+/// ```
+#[allow(unused_variables)]
+pub trait Observer<S: State, M: Message>: Send + Sync {
+    /// The given node starts a communication round in the given state
+    fn round_start(&self, node: usize, round: u32, state: &S) {}
+
+    /// The given node is about to send a message to the given port
+    fn message_sent(&self, node: usize, round: u32, port: usize, message: &M) {}
+
+    /// The given node received a message from the given port
+    fn message_received(&self, node: usize, round: u32, port: usize, message: &M) {}
+
+    /// The given node transitioned to a different state at the end of a round
+    fn state_transition(&self, node: usize, round: u32, from: &S, to: &S) {}
+
+    /// The given node entered a stopping (output) state, called once per node
+    fn output_reached(&self, node: usize, round: u32, state: &S) {}
+
+    /// The given node completed a communication round, ending up in the given state
+    fn round_end(&self, node: usize, round: u32, state: &S) {}
+
+    /// The message sent by the given node to the given port was affected by the given fault, see
+    /// `faults::MessageFaults`
+    fn message_faulted(&self, node: usize, round: u32, port: usize, fault: MessageFault) {}
+
+    /// The given node crashed at the beginning of the given round, see `faults::Crashes`
+    fn crashed(&self, node: usize, round: u32) {}
+
+    /// The given node hit the timeout while sending or receiving in the given round
+    fn timeout(&self, node: usize, round: u32) {}
+
+    /// The simulation has finished with the given result
+    fn finished(&self, result: &RunResult) {}
+}
@@ -0,0 +1,157 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::verifier::Ports;
+
+/// Largest network (in nodes) for which the exact optimum is computed, the centralized solvers
+/// take exponential time in the worst case
+pub const MAX_NODES: usize = 32;
+
+/// Size of a solution computed by an algorithm compared to the optimum of the network
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Approximation {
+    /// Objective value of the solution, e.g. the number of nodes in a vertex cover
+    pub value: usize,
+    /// Optimal objective value in the network
+    pub optimum: usize,
+    /// Whether the objective is maximized instead of minimized
+    pub maximize: bool,
+}
+
+impl Approximation {
+    /// Achieved approximation ratio, at least 1 for any valid solution. Infinite if the solution
+    /// of a maximization problem is empty.
+    pub fn ratio(&self) -> f64 {
+        match self.maximize {
+            true => self.optimum as f64 / self.value as f64,
+            false => self.value as f64 / self.optimum as f64,
+        }
+    }
+}
+
+/// Convert the network into neighborhood bitmasks
+fn masks(ports: &Ports) -> Vec<u64> {
+    assert!(ports.len() <= MAX_NODES, "the network is too large for the exact solvers");
+    ports
+        .iter()
+        .map(|n| n.iter().fold(0, |m, v| m | 1 << v))
+        .collect()
+}
+
+/// Iterate over the indices of the set bits of the given mask
+fn bits(mut mask: u64) -> impl Iterator<Item=usize> {
+    std::iter::from_fn(move || {
+        let i = mask.trailing_zeros() as usize;
+        mask &= mask.wrapping_sub(1);
+        (i < 64).then_some(i)
+    })
+}
+
+/// Size of a minimum vertex cover of the network, using branch and bound. At most `MAX_NODES`
+/// nodes are supported.
+pub fn min_vertex_cover(ports: &Ports) -> usize {
+    fn search(adj: &[u64], cover: u64, size: usize, best: &mut usize) {
+        if size >= *best {
+            return;
+        }
+
+        // Branch on a node with uncovered edges: either it or all of its neighbors are in the cover
+        match (0..adj.len()).find(|u| cover & 1 << u == 0 && adj[*u] & !cover != 0) {
+            None => *best = size,
+            Some(u) => {
+                let n = adj[u] & !cover;
+                search(adj, cover | n, size + n.count_ones() as usize, best);
+                search(adj, cover | 1 << u, size + 1, best);
+            }
+        }
+    }
+
+    let adj = masks(ports);
+    let mut best = adj.len();
+    search(&adj, 0, 0, &mut best);
+    best
+}
+
+/// Size of a maximum matching of the network in edges, using branch and bound. At most
+/// `MAX_NODES` nodes are supported.
+pub fn max_matching(ports: &Ports) -> usize {
+    fn search(adj: &[u64], free: u64, size: usize, best: &mut usize) {
+        *best = size.max(*best);
+
+        // Only free nodes with free neighbors can still be matched
+        let candidates = bits(free).filter(|u| adj[*u] & free != 0).fold(0u64, |m, u| m | 1 << u);
+        if size + candidates.count_ones() as usize / 2 <= *best {
+            return;
+        }
+
+        // Branch on the first candidate: it is matched to one of its free neighbors or not at all
+        let u = candidates.trailing_zeros() as usize;
+        for v in bits(adj[u] & free) {
+            search(adj, free & !(1 << u | 1 << v), size + 1, best);
+        }
+        search(adj, free & !(1 << u), size, best);
+    }
+
+    let adj = masks(ports);
+    let mut best = 0;
+    search(&adj, (0..adj.len()).fold(0, |m, u| m | 1 << u), 0, &mut best);
+    best
+}
+
+/// Size of a minimum dominating set of the network, using branch and bound. At most `MAX_NODES`
+/// nodes are supported.
+pub fn min_dominating_set(ports: &Ports) -> usize {
+    fn search(closed: &[u64], all: u64, dominated: u64, size: usize, best: &mut usize) {
+        if dominated == all {
+            *best = size.min(*best);
+            return;
+        }
+
+        if size + 1 >= *best {
+            return;
+        }
+
+        // Branch on the first undominated node: it or one of its neighbors is in the set
+        let v = (all & !dominated).trailing_zeros() as usize;
+        for u in bits(closed[v]) {
+            search(closed, all, dominated | closed[u], size + 1, best);
+        }
+    }
+
+    // Closed neighborhoods, i.e. including the node itself
+    let closed: Vec<_> = masks(ports).into_iter().enumerate().map(|(v, m)| m | 1 << v).collect();
+    let mut best = closed.len();
+    search(&closed, (0..closed.len()).fold(0, |m, u| m | 1 << u), 0, 0, &mut best);
+    best
+}
+
+/// Chromatic number of the network, using backtracking for increasing numbers of colours. At most
+/// `MAX_NODES` nodes are supported.
+pub fn chromatic_number(ports: &Ports) -> usize {
+    fn color(adj: &[u64], colors: &mut [usize], v: usize, used: usize, k: usize) -> bool {
+        if v == adj.len() {
+            return true;
+        }
+
+        // Nodes are colored in order, so it suffices to try one previously unused colour
+        for c in 0..k.min(used + 1) {
+            if bits(adj[v]).filter(|u| *u < v).all(|u| colors[u] != c) {
+                colors[v] = c;
+                if color(adj, colors, v + 1, used.max(c + 1), k) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    let adj = masks(ports);
+    let mut colors = vec![0; adj.len()];
+    (1..=adj.len()).find(|k| color(&adj, &mut colors, 0, 0, *k)).unwrap_or(0)
+}
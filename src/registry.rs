@@ -0,0 +1,219 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use crate::algorithms::*;
+use crate::builder::Options;
+use crate::optimum::Approximation;
+use crate::simulator::{DaSimulator, RunResult};
+use crate::types::*;
+use crate::verifier::Violation;
+
+/// Parameter values for an algorithm by parameter name, given as strings to be parsed on lookup
+pub type Params = HashMap<String, String>;
+
+/// Type-erased interface to a simulator running some algorithm. This hides the state and message
+/// types of the algorithm, so that simulations of different algorithms can be handled uniformly.
+pub trait Simulation {
+    /// Name of the simulated algorithm
+    fn algorithm_name(&self) -> String;
+
+    /// See `DaSimulator::record_trace`
+    fn record_trace(&mut self, enabled: bool);
+
+    /// See `DaSimulator::run`
+    fn run(&mut self) -> RunResult;
+
+    /// See `DaSimulator::check_scheduling`, the divergence is returned formatted
+    fn check_scheduling(&mut self, runs: u32, max_jitter: Duration) -> Result<RunResult, String>;
+
+    /// See `DaSimulator::run_tui`
+    fn run_tui(&mut self) -> io::Result<RunResult>;
+
+    /// See `DaSimulator::print`
+    fn print(&self, node_ids: bool);
+
+    /// See `DaSimulator::to_json`
+    fn to_json(&self) -> serde_json::Value;
+
+    /// See `DaSimulator::write_frames`
+    fn write_frames(&self, dir: &Path, message_labels: bool) -> io::Result<usize>;
+
+    /// See `DaSimulator::write_html`
+    fn write_html(&self, path: &Path) -> io::Result<()>;
+
+    /// See `DaSimulator::solution_size`
+    fn solution_size(&self) -> Option<usize>;
+
+    /// See `DaSimulator::verify`
+    fn verify(&self) -> Option<Result<(), Vec<Violation>>>;
+
+    /// See `DaSimulator::approximation`
+    fn approximation(&self) -> Option<Approximation>;
+}
+
+impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> Simulation for DaSimulator<A, S, M> {
+    fn algorithm_name(&self) -> String {
+        self.algorithm().name()
+    }
+
+    fn record_trace(&mut self, enabled: bool) {
+        self.record_trace(enabled)
+    }
+
+    fn run(&mut self) -> RunResult {
+        self.run()
+    }
+
+    fn check_scheduling(&mut self, runs: u32, max_jitter: Duration) -> Result<RunResult, String> {
+        self.check_scheduling(runs, max_jitter).map_err(|d| d.to_string())
+    }
+
+    fn run_tui(&mut self) -> io::Result<RunResult> {
+        self.run_tui()
+    }
+
+    fn print(&self, node_ids: bool) {
+        self.print(node_ids)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        self.to_json()
+    }
+
+    fn write_frames(&self, dir: &Path, message_labels: bool) -> io::Result<usize> {
+        self.write_frames(dir, message_labels)
+    }
+
+    fn write_html(&self, path: &Path) -> io::Result<()> {
+        self.write_html(path)
+    }
+
+    fn solution_size(&self) -> Option<usize> {
+        self.solution_size()
+    }
+
+    fn verify(&self) -> Option<Result<(), Vec<Violation>>> {
+        self.verify()
+    }
+
+    fn approximation(&self) -> Option<Approximation> {
+        self.approximation()
+    }
+}
+
+/// Description of a parameter accepted by an algorithm
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: &'static str,
+}
+
+/// Function for constructing a simulation of the algorithm of the given entry on the given network
+/// with the given options and parameters
+type Builder = fn(&Entry, &[(u32, u32)], &Options, &Params) -> Result<Box<dyn Simulation>, String>;
+
+/// Registry entry for an algorithm that can be looked up and run by name
+pub struct Entry {
+    /// Name used for looking up the algorithm
+    pub name: &'static str,
+    /// Short description of what the algorithm does and what it requires from the network
+    pub description: &'static str,
+    /// Parameters accepted by the algorithm
+    pub parameters: &'static [Parameter],
+    builder: Builder,
+}
+
+impl Entry {
+    /// Construct a simulation of the algorithm on the given network with the given options and
+    /// parameters. Parameters that are not given use their default values.
+    pub fn build(&self, edges: &[(u32, u32)], options: &Options, params: &Params) -> Result<Box<dyn Simulation>, String> {
+        if let Some(p) = params.keys().find(|p| self.parameters.iter().all(|q| q.name != *p)) {
+            return Err(format!("unknown parameter for {}: {p}", self.name));
+        }
+
+        (self.builder)(self, edges, options, params)
+    }
+
+    /// Look up the value of the given parameter, falling back to its default value
+    fn param<T: std::str::FromStr>(&self, params: &Params, name: &str) -> Result<T, String>
+        where T::Err: std::fmt::Display
+    {
+        let p = self.parameters.iter().find(|p| p.name == name).expect("undeclared parameter");
+        let value = params.get(name).map_or(p.default, |v| v.as_str());
+        value.parse().map_err(|e| format!("invalid value for {name}: {value}: {e}"))
+    }
+}
+
+/// Construct a simulation of the given algorithm instance
+fn simulation<A, S, M>(algorithm: A, edges: &[(u32, u32)], options: &Options) -> Result<Box<dyn Simulation>, String>
+    where A: DistributedAlgorithm<S, M> + 'static, S: State + 'static, M: Message + Clone + 'static
+{
+    let simulator = DaSimulator::builder(algorithm)
+        .network(edges)
+        .options(options.clone())
+        .message_faults(options.message_faults.clone())
+        .build()?;
+
+    Ok(Box::new(simulator))
+}
+
+/// Construct a simulation of an unparametrised algorithm
+fn build<A, S, M>(_: &Entry, edges: &[(u32, u32)], options: &Options, _: &Params) -> Result<Box<dyn Simulation>, String>
+    where A: DistributedAlgorithm<S, M> + Default + 'static, S: State + 'static, M: Message + Clone + 'static
+{
+    simulation(A::default(), edges, options)
+}
+
+/// Construct a simulation of the Isomorphic Neighborhood algorithm with the given depth
+fn build_isomorphic(entry: &Entry, edges: &[(u32, u32)], options: &Options, params: &Params) -> Result<Box<dyn Simulation>, String> {
+    let depth = entry.param(params, "depth")?;
+    simulation(IsomorphicNeighborhood::new(depth), edges, options)
+}
+
+/// All the algorithms available in the registry
+static ALGORITHMS: &[Entry] = &[
+    Entry {
+        name: "bipartite-matching",
+        description: "Bipartite maximal matching in the PN model, requires the network to be \
+            bipartite wrt. even/odd nodes",
+        parameters: &[],
+        builder: build::<BipartiteMaximalMatching, _, _>,
+    },
+    Entry {
+        name: "mvc-3approx",
+        description: "Minimum vertex cover 3-approximation in the PN model, runs the bipartite \
+            maximal matching in a virtual bipartite network",
+        parameters: &[],
+        builder: build::<Mvc3approx, _, _>,
+    },
+    Entry {
+        name: "isomorphic-neighborhood",
+        description: "Sums up node degrees over the neighborhood up to the given depth in the PN \
+            model, a functional test",
+        parameters: &[Parameter {
+            name: "depth",
+            description: "Depth of the neighborhood to gather",
+            default: "5",
+        }],
+        builder: build_isomorphic,
+    },
+];
+
+/// List all the algorithms available in the registry
+pub fn algorithms() -> &'static [Entry] {
+    ALGORITHMS
+}
+
+/// Look up an algorithm in the registry by name
+pub fn lookup(name: &str) -> Option<&'static Entry> {
+    ALGORITHMS.iter().find(|e| e.name == name)
+}
@@ -0,0 +1,247 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::Deserialize;
+use crate::builder::Options;
+use crate::faults::Crashes;
+use crate::generators::EdgeSet;
+use crate::network;
+use crate::optimum::Approximation;
+use crate::registry::{self, Params, Simulation};
+use crate::simulator::RunResult;
+use crate::types::Inputs;
+
+/// Declarative description of a complete simulation, loaded from a TOML file. For example:
+///
+/// ```toml
+/// description = "MVC 3-approximation on a cycle with random port numbering"
+/// algorithm = "mvc-3approx"
+/// ports = "random"
+/// seed = 42
+///
+/// [network]
+/// generator = "cycle:10"
+///
+/// [expect]
+/// success = true
+/// max_rounds = 10
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Free-form description of the scenario
+    #[serde(default)]
+    pub description: String,
+    /// Name of the algorithm in the registry
+    pub algorithm: String,
+    /// Parameters for the algorithm, values can be given as strings or TOML scalars
+    #[serde(default)]
+    params: HashMap<String, toml::Value>,
+    network: NetworkSource,
+    /// Port numbering strategy, see `PortNumbering`
+    #[serde(default = "given")]
+    ports: String,
+    /// Node ID assignment strategy, see `IdAssignment`
+    #[serde(default = "given")]
+    ids: String,
+    /// Local inputs of the nodes by node index, after applying the ID assignment. TOML keys are
+    /// always strings, so the indices are parsed when running the scenario.
+    #[serde(default)]
+    inputs: HashMap<String, String>,
+    /// Seed for the random generators and strategies
+    #[serde(default)]
+    pub seed: u64,
+    /// Timeout in seconds for deadlock prevention
+    #[serde(default = "default_timeout")]
+    pub timeout: f64,
+    /// Communication round limit, unlimited if not given
+    pub round_limit: Option<u32>,
+    /// Nodes crashing in the given rounds given as `[node, round]` pairs, see `Crashes`
+    #[serde(default)]
+    crashes: Vec<(usize, u32)>,
+    /// Expected outcome of the simulation
+    #[serde(default)]
+    pub expect: Expectation,
+    // Directory of the scenario file, relative network files are resolved against it
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+/// Source of the network of a scenario, exactly one of the fields must be given
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkSource {
+    /// File to read the network from, relative to the scenario file
+    file: Option<PathBuf>,
+    /// Generator for the network, see `network::generate`
+    generator: Option<String>,
+    /// Edges of the network given inline as pairs of node indices
+    edges: Option<EdgeSet>,
+}
+
+/// Expected outcome of a scenario, only the given fields are checked
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    /// Whether every node reaches a stopping state
+    pub success: Option<bool>,
+    /// Exact number of communication rounds
+    pub rounds: Option<u32>,
+    /// Maximum number of communication rounds
+    pub max_rounds: Option<u32>,
+    /// Number of nodes that reach a stopping state
+    pub stopped: Option<usize>,
+    /// Size of the solution, see `State::in_solution`
+    pub solution: Option<usize>,
+    /// Maximum approximation ratio compared to the exact optimum, see `DaSimulator::approximation`
+    pub max_ratio: Option<f64>,
+}
+
+/// Outcome of running a scenario
+pub struct Outcome {
+    /// The finished simulation for producing further output
+    pub simulation: Box<dyn Simulation>,
+    pub result: RunResult,
+    /// Number of edges in the network
+    pub edges: usize,
+    /// Descriptions of the expectations that were not met
+    pub failures: Vec<String>,
+}
+
+/// Default value for the port numbering and ID assignment strategies
+fn given() -> String {
+    "given".into()
+}
+
+/// Default value for the timeout in seconds
+fn default_timeout() -> f64 {
+    5.0
+}
+
+impl Scenario {
+    /// Load a scenario from the given TOML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut scenario: Scenario = toml::from_str(&content).map_err(|e| format!("{}: {e}", path.display()))?;
+        scenario.base_dir = path.parent().map_or(PathBuf::new(), Path::to_path_buf);
+        Ok(scenario)
+    }
+
+    /// Build the network of the scenario, the port numbering and ID assignment are applied by the
+    /// simulator
+    pub fn edges(&self) -> Result<EdgeSet, String> {
+        match &self.network {
+            NetworkSource { file: Some(file), generator: None, edges: None } => {
+                let path = self.base_dir.join(file);
+                network::read_edges(&path).map_err(|e| format!("{}: {e}", path.display()))
+            }
+            NetworkSource { file: None, generator: Some(spec), edges: None } => network::generate(spec, self.seed),
+            NetworkSource { file: None, generator: None, edges: Some(edges) } => Ok(edges.clone()),
+            _ => Err("exactly one of network.file, network.generator and network.edges must be given".into()),
+        }
+    }
+
+    /// Run the scenario and check the outcome against the expectations
+    pub fn run(&self) -> Result<Outcome, String> {
+        let entry = registry::lookup(&self.algorithm)
+            .ok_or_else(|| format!("unknown algorithm: {}", self.algorithm))?;
+
+        let params: Params = self.params
+            .iter()
+            .map(|(k, v)| match v {
+                toml::Value::String(s) => (k.clone(), s.clone()),
+                v => (k.clone(), v.to_string()),
+            })
+            .collect();
+
+        let inputs = self.inputs
+            .iter()
+            .map(|(k, v)| k.parse().map(|i| (i, v.clone())).map_err(|e| format!("invalid node index in inputs: {k}: {e}")))
+            .collect::<Result<Inputs, _>>()?;
+
+        let options = Options {
+            timeout: Duration::try_from_secs_f64(self.timeout)
+                .map_err(|e| format!("invalid timeout {}: {e}", self.timeout))?,
+            round_limit: self.round_limit,
+            ports: self.ports.parse()?,
+            ids: self.ids.parse()?,
+            seed: self.seed,
+            inputs,
+            crashes: match self.crashes.is_empty() {
+                true => Crashes::None,
+                false => Crashes::Schedule(self.crashes.clone()),
+            },
+            ..Options::default()
+        };
+
+        let edges = self.edges()?;
+        let mut simulation = entry.build(&edges, &options, &params)?;
+        let result = simulation.run();
+        let mut failures = self.expect.check(&result, simulation.solution_size(), simulation.approximation());
+
+        // The algorithm must never panic, regardless of the expected outcome
+        if let Some(p) = &result.panic {
+            failures.push(format!("node {} panicked in {} in round {}: {}", p.node, p.call, p.round, p.message));
+        }
+
+        // Complete solutions of known problems must always be valid
+        if let Some(Err(violations)) = simulation.verify().filter(|_| result.complete()) {
+            failures.extend(violations.iter().map(|v| format!("invalid solution: {v}")));
+        }
+
+        Ok(Outcome { simulation, result, edges: edges.len(), failures })
+    }
+}
+
+impl Expectation {
+    /// Check the result of a run against the expectations, returning the unmet ones. If no
+    /// expectations are given, every node is expected to reach a stopping state.
+    fn check(&self, result: &RunResult, solution: Option<usize>, approximation: Option<Approximation>) -> Vec<String> {
+        let mut failures = Vec::new();
+        let mut expect = |ok: bool, msg: String| if !ok { failures.push(msg) };
+
+        // Without any expectations, the scenario is expected to terminate successfully
+        let success = match self {
+            Expectation { success: None, rounds: None, max_rounds: None, stopped: None, solution: None, max_ratio: None } => Some(true),
+            _ => self.success,
+        };
+
+        if let Some(success) = success {
+            expect(result.success() == success, format!("expected success = {success}, got {}", result.success()));
+        }
+
+        if let Some(rounds) = self.rounds {
+            expect(result.rounds == rounds, format!("expected {rounds} rounds, got {}", result.rounds));
+        }
+
+        if let Some(max_rounds) = self.max_rounds {
+            expect(result.rounds <= max_rounds, format!("expected at most {max_rounds} rounds, got {}", result.rounds));
+        }
+
+        if let Some(stopped) = self.stopped {
+            expect(result.stopped == stopped, format!("expected {stopped} stopped nodes, got {}", result.stopped));
+        }
+
+        if let Some(size) = self.solution {
+            expect(solution == Some(size), format!("expected solution size {size}, got {solution:?}"));
+        }
+
+        if let Some(max_ratio) = self.max_ratio {
+            let ratio = approximation.map(|a| a.ratio());
+            expect(ratio.is_some_and(|r| r <= max_ratio),
+                   format!("expected an approximation ratio of at most {max_ratio}, got {ratio:?}"));
+        }
+
+        failures
+    }
+}
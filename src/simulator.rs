@@ -6,10 +6,14 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use std::any::Any;
 use std::collections::{HashSet, VecDeque};
-use std::marker::PhantomData;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use crossbeam_channel::{RecvTimeoutError, SendTimeoutError};
@@ -17,21 +21,415 @@ use petgraph::{Graph, IntoWeightedEdge, Undirected};
 use petgraph::dot::{Config, Dot};
 use petgraph::graph::{DefaultIx, EdgeReference};
 use petgraph::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde_json::json;
+use crate::adversary::{Adversary, Board, View};
+use crate::builder::{Jitter, MessageSize, Options, SimulatorBuilder};
+use crate::faults::{Crashes, FaultCounts, Injector, MessageFaults, Mutators};
 use crate::types::*;
+use crate::observer::Observer;
+use crate::optimum::Approximation;
+use crate::tui::{self, Monitor};
+use crate::verifier::Violation;
+use crate::visualizer::{self, EdgeView, Frame, NodeView};
 
 /// A highly parallel simulator capable of running arbitrary distributed algorithms of various
 /// models of computation (PN, LOCAL, CONGEST) on networks constructed from arbitrary graphs.
 pub struct DaSimulator<A: DistributedAlgorithm<S, M>, S: State, M: Message> {
-    a: PhantomData<A>,
-    // This is required to keep the algorithm in scope since it is stateless
+    algorithm: A,
     graph: Graph<S, Edge<M>, Undirected>,
     timeout: Duration,
+    round_limit: Option<u32>,
+    // CONGEST bandwidth limit in bits, requires the message size function
+    bandwidth: Option<u32>,
+    message_size: Option<MessageSize<M>>,
+    tracing: bool,
+    trace: Vec<Vec<RoundRecord<S>>>,
+    jitter: Option<Jitter>,
+    crashes: Crashes,
+    message_faults: MessageFaults,
+    mutators: Mutators<M>,
+    // Whether each node is Byzantine by node index, see `Adversary`
+    byzantine: Vec<bool>,
+    adversary: Box<dyn Adversary<S, M>>,
+    observers: Vec<Arc<dyn Observer<S, M>>>,
+}
+
+/// Summary of a completed simulation run
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    /// Number of nodes in the network
+    pub nodes: usize,
+    /// Number of honest nodes that reached a stopping state, Byzantine nodes never stop
+    pub stopped: usize,
+    /// Number of communication rounds executed by the longest-running node
+    pub rounds: u32,
+    /// Total number of messages sent by all nodes, including no-op messages
+    pub messages: u64,
+    /// Number of edges in the network
+    pub edges: usize,
+    /// Round at the end of which each node entered a stopping state by node index, `None` for
+    /// nodes that did not stop
+    pub stopping_rounds: Vec<Option<u32>>,
+    /// Number of sent messages marked as no-ops, see `Message::is_noop`
+    pub noops: u64,
+    /// Total size of the sent messages excluding no-ops in bits, `None` if no message size
+    /// function is given, see `SimulatorBuilder::message_size`
+    pub bits: Option<u64>,
+    /// Earliest panic of the algorithm in a node, which aborts the run
+    pub panic: Option<NodePanic>,
+    /// Round in which each node crashed by node index, `None` for nodes that did not crash, see
+    /// `faults::Crashes`
+    pub crashes: Vec<Option<u32>>,
+    /// Number of messages affected by faults, see `faults::MessageFaults`
+    pub faults: FaultCounts,
+    /// Whether each node was Byzantine by node index, see `adversary::Adversary`
+    pub byzantine: Vec<bool>,
+}
+
+impl RunResult {
+    /// Determines if all honest nodes that did not crash reached a stopping state without the
+    /// algorithm panicking
+    pub fn success(&self) -> bool {
+        let done = self.stopping_rounds
+            .iter()
+            .zip(&self.crashes)
+            .zip(&self.byzantine)
+            .all(|((s, c), b)| s.is_some() || c.is_some() || *b);
+        done && self.panic.is_none()
+    }
+
+    /// Determines if all nodes reached a stopping state, i.e. the outputs form a complete solution
+    /// that can be verified. Nodes that crashed before stopping and Byzantine nodes leave the
+    /// solution incomplete.
+    pub fn complete(&self) -> bool {
+        self.stopped == self.nodes
+    }
+
+    /// Number of nodes that crashed
+    pub fn crashed(&self) -> usize {
+        self.crashes.iter().flatten().count()
+    }
+
+    /// Number of Byzantine nodes
+    pub fn byzantine_nodes(&self) -> usize {
+        self.byzantine.iter().filter(|b| **b).count()
+    }
+
+    /// Latest stopping round of the nodes, i.e. the worst-case round complexity of the run.
+    /// `None` if no node stopped.
+    pub fn max_stopping_round(&self) -> Option<u32> {
+        self.stopping_rounds.iter().flatten().max().copied()
+    }
+
+    /// Average stopping round of the nodes that stopped, i.e. the node-averaged round complexity
+    /// of the run. `None` if no node stopped.
+    pub fn average_stopping_round(&self) -> Option<f64> {
+        let rounds: Vec<_> = self.stopping_rounds.iter().flatten().collect();
+        (!rounds.is_empty()).then(|| rounds.iter().map(|r| **r as f64).sum::<f64>() / rounds.len() as f64)
+    }
+
+    /// Number of sent messages excluding no-ops
+    pub fn useful_messages(&self) -> u64 {
+        self.messages - self.noops
+    }
+
+    /// Average number of messages excluding no-ops sent over a single edge
+    pub fn messages_per_edge(&self) -> f64 {
+        self.useful_messages() as f64 / self.edges as f64
+    }
+
+    /// Totals of the run that must not depend on the scheduling, see `DaSimulator::check_scheduling`
+    fn totals(&self) -> [(&'static str, u64); 5] {
+        [
+            ("rounds", self.rounds as u64),
+            ("messages", self.messages),
+            ("dropped messages", self.faults.dropped),
+            ("duplicated messages", self.faults.duplicated),
+            ("corrupted messages", self.faults.corrupted),
+        ]
+    }
+
+    /// Number of nodes that stopped in each round, indexed by the round number
+    pub fn stopping_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.max_stopping_round().map_or(0, |r| r as usize + 1)];
+        self.stopping_rounds.iter().flatten().for_each(|r| histogram[*r as usize] += 1);
+        histogram
+    }
+}
+
+/// Function of the algorithm called by a node, see `NodePanic`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Call {
+    Send,
+    Receive,
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Call::Send => write!(f, "send"),
+            Call::Receive => write!(f, "receive"),
+        }
+    }
+}
+
+/// Panic of `DistributedAlgorithm::send` or `DistributedAlgorithm::receive` in a node, or a
+/// violation of the model detected in the call, i.e. a message exceeding the CONGEST bandwidth or
+/// a state transition after stopping. The panicking node leaves the simulation, closing its
+/// channels, which makes the other nodes stop as well within a few rounds.
+#[derive(Clone, Debug)]
+pub struct NodePanic {
+    pub node: usize,
+    pub round: u32,
+    pub call: Call,
+    /// State of the node before the call, formatted with `Debug`
+    pub state: String,
+    /// Messages received by the node by port, formatted with `Debug`. Empty for `Call::Send`.
+    pub messages: Vec<String>,
+    /// Panic message, e.g. "called `Option::unwrap()` on a `None` value"
+    pub message: String,
+}
+
+impl NodePanic {
+    /// Capture a panic of the given call for the node, recording the panic payload as the message
+    fn new(node: usize, round: u32, call: Call, state: &impl fmt::Debug, messages: Vec<String>, payload: Box<dyn Any + Send>) -> Self {
+        let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(s), _) => s.to_string(),
+            (_, Some(s)) => s.clone(),
+            _ => "unknown panic payload".into(),
+        };
+
+        Self { node, round, call, state: format!("{state:?}"), messages, message }
+    }
+}
+
+impl fmt::Display for NodePanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "node {} panicked in {} in round {}: {}", self.node, self.call, self.round, self.message)?;
+        write!(f, "  state: {}", self.state)?;
+        for (port, m) in self.messages.iter().enumerate() {
+            write!(f, "\n  port {}: {m}", port + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Divergence of a run from the first one, see `DaSimulator::check_scheduling`
+#[derive(Clone, Debug)]
+pub struct Divergence<S: State> {
+    /// Index of the diverging run, starting from 0 for the first run
+    pub run: u32,
+    /// Nodes whose final state or stopping round differs between the runs
+    pub nodes: Vec<NodeDiff<S>>,
+    /// Totals of the runs that differ, given as tuples of the name, the total of the first run
+    /// and the total of the diverging run, e.g. `("rounds", 3, 4)`
+    pub totals: Vec<(&'static str, u64, u64)>,
+}
+
+/// Final state and stopping round of a node in the first and the diverging run
+#[derive(Clone, Debug)]
+pub struct NodeDiff<S: State> {
+    pub node: usize,
+    pub expected: (S, Option<u32>),
+    pub actual: (S, Option<u32>),
+}
+
+impl<S: State> fmt::Display for Divergence<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Format a node in the style of a unified diff
+        let line = |f: &mut fmt::Formatter<'_>, sign, node, (state, round): &(S, Option<u32>)| match round {
+            Some(r) => writeln!(f, "{sign} node {node}: {state:?} (stopped in round {r})"),
+            None => writeln!(f, "{sign} node {node}: {state:?} (running)"),
+        };
+
+        writeln!(f, "run {} diverged from run 0 in {} node(s) and {} total(s):", self.run, self.nodes.len(), self.totals.len())?;
+        for d in &self.nodes {
+            line(f, '-', d.node, &d.expected)?;
+            line(f, '+', d.node, &d.actual)?;
+        }
+
+        for (name, expected, actual) in &self.totals {
+            writeln!(f, "- {name}: {expected}")?;
+            writeln!(f, "+ {name}: {actual}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Statistics collected by a single node thread
+struct NodeStats {
+    rounds: u32,
+    stopped: Option<u32>,
+    sent: u64,
+    noops: u64,
+    bits: u64,
+    panic: Option<NodePanic>,
+    crashed: Option<u32>,
+    faults: FaultCounts,
+}
+
+/// Progress of a single node thread, see `Termination`
+#[derive(Clone, Copy)]
+struct Progress {
+    // Number of completed communication rounds
+    completed: u32,
+    // Round after which the node no longer needs to run, i.e. its stopping round, the round before
+    // its crash or 0 for Byzantine nodes
+    done: Option<u32>,
+    // Whether the node thread has finished
+    left: bool,
+}
+
+/// Decides when the simulation terminates, which is after the first round at the end of which all
+/// nodes have stopped, crashed or are Byzantine. A node that is done waits for the others to
+/// complete the round before deciding, so all nodes terminate after the same round regardless of
+/// the scheduling of the node threads.
+struct Termination {
+    nodes: Mutex<Vec<Progress>>,
+    condvar: Condvar,
+}
+
+impl Termination {
+    /// Track nodes that are done after the given rounds from the start, see `Progress`
+    fn new(done: impl Iterator<Item=Option<u32>>) -> Self {
+        Self {
+            nodes: Mutex::new(done.map(|done| Progress { completed: 0, done, left: false }).collect()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Record that the given node completed the given round, possibly stopping in it
+    fn complete(&self, node: usize, round: u32, stopped: bool) {
+        let p = &mut self.nodes.lock().unwrap()[node];
+        p.completed = round;
+        if stopped {
+            p.done = Some(p.done.map_or(round, |d| d.min(round)));
+        }
+
+        self.condvar.notify_all();
+    }
+
+    /// Record that the thread of the given node has finished
+    fn leave(&self, node: usize) {
+        self.nodes.lock().unwrap()[node].left = true;
+        self.condvar.notify_all();
+    }
+
+    /// Determine if the simulation terminates after the given round completed by the given node,
+    /// waiting for the other nodes to complete it if necessary. `false` if the deadline is reached.
+    fn terminates(&self, node: usize, round: u32, deadline: Instant) -> bool {
+        let done = |p: &Progress| p.done.is_some_and(|d| d <= round);
+        let mut nodes = self.nodes.lock().unwrap();
+        if !done(&nodes[node]) {
+            return false;
+        }
+
+        loop {
+            // A node that has completed the round or left can no longer become done in time
+            let decided = |p: &Progress| p.completed >= round || p.left;
+            if nodes.iter().any(|p| decided(p) && !done(p)) {
+                return false;
+            }
+
+            if nodes.iter().all(decided) {
+                return true;
+            }
+
+            let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+                return false;
+            };
+
+            nodes = self.condvar.wait_timeout(nodes, timeout).unwrap().0;
+        }
+    }
+}
+
+/// Record of a single node in a single communication round, used for exporting frames
+struct RoundRecord<S: State> {
+    // State of the node at the beginning of the round
+    state: S,
+    // Formatted messages sent to each port of the node during the round by port, `None` for the
+    // ports that were not sent a message
+    messages: Vec<Option<String>>,
+}
+
+/// Observer recording the states and sent messages of every node in every round
+struct Tracer<S: State> {
+    nodes: Vec<Mutex<Vec<RoundRecord<S>>>>,
+}
+
+impl<S: State> Tracer<S> {
+    fn new(node_count: usize) -> Self {
+        Self { nodes: (0..node_count).map(|_| Mutex::default()).collect() }
+    }
+
+    /// Retrieve the recorded rounds of every node
+    fn into_trace(self) -> Vec<Vec<RoundRecord<S>>> {
+        self.nodes.into_iter().map(|n| n.into_inner().unwrap()).collect()
+    }
+}
+
+impl<S: State, M: Message> Observer<S, M> for Tracer<S> {
+    fn round_start(&self, node: usize, _: u32, state: &S) {
+        self.nodes[node].lock().unwrap().push(RoundRecord {
+            state: state.clone(),
+            messages: Vec::new(),
+        });
+    }
+
+    fn message_sent(&self, node: usize, _: u32, port: usize, message: &M) {
+        if let Some(r) = self.nodes[node].lock().unwrap().last_mut() {
+            if r.messages.len() <= port {
+                r.messages.resize(port + 1, None);
+            }
+
+            r.messages[port] = Some(format!("{:?}", message));
+        }
+    }
 }
 
 impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
-    /// Construct a new simulator that builds a new network from the given set of edges (the order
-    /// of which determines the port numbering) and has the given timeout for deadlock prevention
-    pub fn from_network(edges: &[(u32, u32)], timeout: Duration) -> Self {
+    /// Create a builder for configuring a simulator for the given algorithm instance (carrying its
+    /// parameters), see `SimulatorBuilder`
+    pub fn builder(algorithm: A) -> SimulatorBuilder<A, S, M> {
+        SimulatorBuilder::new(algorithm)
+    }
+
+    /// Construct a new simulator for the default instance of the algorithm, see `new`
+    pub fn from_network(edges: &[(u32, u32)], timeout: Duration) -> Self
+        where A: Default
+    {
+        Self::new(A::default(), edges, timeout)
+    }
+
+    /// Construct a new simulator for the given algorithm instance that builds a new network from
+    /// the given set of edges (the order of which determines the port numbering) and has the given
+    /// timeout for deadlock prevention. Panics if the network is invalid, use `builder` for more
+    /// options and proper error handling.
+    pub fn new(algorithm: A, edges: &[(u32, u32)], timeout: Duration) -> Self {
+        Self::builder(algorithm)
+            .network(edges)
+            .timeout(timeout)
+            .build()
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Construct a new simulator from options validated by `SimulatorBuilder`
+    pub(crate) fn construct(
+        algorithm: A,
+        edges: &[(u32, u32)],
+        options: &Options,
+        message_size: Option<MessageSize<M>>,
+        mutators: Mutators<M>,
+        adversary: Box<dyn Adversary<S, M>>,
+        observers: Vec<Arc<dyn Observer<S, M>>>,
+    ) -> Self {
+        let inputs = &options.inputs;
+
         // Count the required amount of nodes from the given edges
         let node_count = 1 + edges
             .iter()
@@ -51,10 +449,11 @@ impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
             .into_iter()
             .enumerate()
             .map(|(node_id, node_degree)|
-                graph.add_node(A::init(&Input {
+                graph.add_node(algorithm.init(&Input {
                     node_id: node_id as u32,
                     node_count,
                     node_degree,
+                    local_input: inputs.get(&(node_id as u32)).map(|i| i.as_str()),
                 }))
             ).collect();
 
@@ -84,25 +483,185 @@ impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
         });
 
         Self {
-            a: PhantomData,
+            algorithm,
             graph,
-            timeout,
+            timeout: options.timeout,
+            round_limit: options.round_limit,
+            bandwidth: options.bandwidth,
+            message_size,
+            tracing: options.trace,
+            trace: Vec::new(),
+            jitter: options.jitter,
+            crashes: options.crashes.clone(),
+            message_faults: options.message_faults.clone(),
+            mutators,
+            byzantine: (0..node_count as usize).map(|v| options.byzantine.contains(&v)).collect(),
+            adversary,
+            observers,
         }
     }
 
+    /// Retrieve the simulated algorithm instance
+    pub fn algorithm(&self) -> &A {
+        &self.algorithm
+    }
+
+    /// Retrieve the current states of all nodes in order of node indices
+    pub fn states(&self) -> Vec<S> {
+        self.graph.node_weights().cloned().collect()
+    }
+
+    /// Retrieve the local outputs of all nodes in order of node indices, `None` for nodes that did
+    /// not reach a stopping state
+    pub fn outputs(&self) -> Vec<Option<Output<S::Output>>> {
+        self.graph.node_weights().map(|s| s.output()).collect()
+    }
+
+    /// Determine the size of the solution computed by the algorithm, i.e. the number of nodes in
+    /// the solution as determined by `State::in_solution`, or `None` if there is no such solution
+    pub fn solution_size(&self) -> Option<usize> {
+        self.graph
+            .node_weights()
+            .map(|s| s.in_solution())
+            .collect::<Option<Vec<_>>>()
+            .map(|v| v.into_iter().filter(|b| *b).count())
+    }
+
+    /// Retrieve the neighbors of all nodes in order of node indices, `ports()[v][p]` is the neighbor
+    /// of node `v` behind its zero-based port `p`
+    pub fn ports(&self) -> Vec<Vec<usize>> {
+        self.graph
+            .node_indices()
+            .map(|i| self.edges(i)
+                .into_iter()
+                .map(|e| if e.source() == i { e.target() } else { e.source() }.index())
+                .collect())
+            .collect()
+    }
+
+    /// Verify the outputs of the nodes against the problem solved by the algorithm, returning the
+    /// violations found, if any. `None` if the algorithm does not declare a problem, see
+    /// `DistributedAlgorithm::problem`.
+    pub fn verify(&self) -> Option<Result<(), Vec<Violation>>> {
+        self.algorithm.problem().map(|p| p.verify(&self.ports(), &self.outputs()))
+    }
+
+    /// Compare the solution computed by the algorithm to the exact optimum of the network. `None`
+    /// if the algorithm does not declare an optimization problem, the solution is invalid or the
+    /// network is too large for computing the optimum, see `optimum::MAX_NODES`.
+    pub fn approximation(&self) -> Option<Approximation> {
+        let problem = self.algorithm.problem()?;
+        let (ports, outputs) = (self.ports(), self.outputs());
+        problem.verify(&ports, &outputs).ok()?;
+
+        Some(Approximation {
+            value: problem.value(&outputs)?,
+            optimum: problem.optimum(&ports)?,
+            maximize: problem.maximize(),
+        })
+    }
+
     /// Retrieve the list of edges attached to the given node in order of port numbers
-    fn edges(&self, node: NodeIndex<DefaultIx>) -> Vec<EdgeReference<Edge<M>>> {
+    fn edges(&self, node: NodeIndex<DefaultIx>) -> Vec<EdgeReference<'_, Edge<M>>> {
         // The edges are iterated in reverse order in `petgraph` so some fiddling is needed here
         let mut vd = VecDeque::new();
         self.graph.edges(node).for_each(|e| vd.push_front(e));
         vd.into()
     }
 
-    /// Run the simulation, optionally terminating after `round_limit` communication rounds if
-    /// `round_limit > 0`. If `round_limit == 0`, run until natural termination.
-    pub fn run(&mut self, round_limit: u32) {
-        println!("\nSimulating the {} algorithm in a PN network with {} nodes and {} edges...",
-                 A::name(), self.graph.node_count(), self.graph.edge_count());
+    /// Enable or disable recording the states and messages of every node in every communication
+    /// round during the simulation, which is required for exporting frames using `write_frames`
+    pub fn record_trace(&mut self, enabled: bool) {
+        self.tracing = enabled;
+    }
+
+    /// Register an observer for instrumenting the following runs, see `Observer`
+    pub fn add_observer(&mut self, observer: Arc<dyn Observer<S, M>>) {
+        self.observers.push(observer);
+    }
+
+    /// Run the simulation until natural termination, or until the round limit is reached if any
+    pub fn run(&mut self) -> RunResult {
+        self.simulate(None)
+    }
+
+    /// Run the simulation the given number of times (at least two) from the initial states with
+    /// the scheduling perturbed by a different `Jitter` of at most `max_jitter` each time, and
+    /// compare the final states and stopping rounds of the nodes as well as the number of rounds,
+    /// messages and faulty messages of the runs. The results must not depend on
+    /// the scheduling, so any divergence points to a bug in the algorithm or the simulator. Returns
+    /// the result of the last run, or the first divergence from the first run. The observers are
+    /// notified of every run.
+    pub fn check_scheduling(&mut self, runs: u32, max_jitter: Duration) -> Result<RunResult, Divergence<S>> {
+        assert!(runs >= 2, "at least two runs are needed for a comparison");
+        let (initial, jitter) = (self.states(), self.jitter);
+        let mut reference: Option<Vec<(S, Option<u32>)>> = None;
+        let mut first: Option<RunResult> = None;
+
+        let mut outcome = None;
+        for run in 0..runs {
+            self.graph.node_weights_mut().zip(&initial).for_each(|(s, i)| *s = i.clone());
+            self.graph.edge_weights().for_each(|e| e.reset());
+            self.jitter = Some(Jitter { max: max_jitter, seed: run as u64 });
+            let result = self.run();
+            let finals: Vec<_> = self.states().into_iter().zip(result.stopping_rounds.iter().copied()).collect();
+
+            if let (Some(expected), Some(first)) = (&reference, &first) {
+                let nodes: Vec<_> = expected
+                    .iter()
+                    .zip(&finals)
+                    .enumerate()
+                    .filter(|(_, (e, a))| e != a)
+                    .map(|(node, (expected, actual))| NodeDiff { node, expected: expected.clone(), actual: actual.clone() })
+                    .collect();
+
+                let totals: Vec<_> = first
+                    .totals()
+                    .into_iter()
+                    .zip(result.totals())
+                    .filter(|(e, a)| e.1 != a.1)
+                    .map(|((name, expected), (_, actual))| (name, expected, actual))
+                    .collect();
+
+                if !nodes.is_empty() || !totals.is_empty() {
+                    self.jitter = jitter;
+                    return Err(Divergence { run, nodes, totals });
+                }
+            } else {
+                reference = Some(finals);
+                first = Some(result.clone());
+            }
+
+            outcome = Some(result);
+        }
+
+        self.jitter = jitter;
+        Ok(outcome.expect("no runs"))
+    }
+
+    /// Run the simulation like `run`, but show a live terminal UI that is redrawn every round and
+    /// allows pausing, stepping and resuming the simulation. See `tui::show` for the controls.
+    pub fn run_tui(&mut self) -> io::Result<RunResult> {
+        let (monitor, events) = Monitor::new();
+        let title = format!("{} ({} nodes, {} edges)",
+                            self.algorithm.name(), self.graph.node_count(), self.graph.edge_count());
+        let node_count = self.graph.node_count();
+
+        thread::scope(|s| {
+            // The UI runs in its own thread while this thread runs the simulation
+            let ui = s.spawn(|| tui::show(&title, node_count, &monitor, events));
+            let result = self.simulate(Some(&monitor));
+            ui.join().expect("UI thread panicked").map(|_| result)
+        }).inspect(|result| self.report(result))
+    }
+
+    /// Run the simulation, reporting the progress to the given monitor if any
+    fn simulate(&mut self, monitor: Option<&Monitor>) -> RunResult {
+        // Writing to stderr would corrupt the terminal UI, which reports the result once closed
+        if monitor.is_none() {
+            eprintln!("\nSimulating the {} algorithm in a PN network with {} nodes and {} edges...",
+                      self.algorithm.name(), self.graph.node_count(), self.graph.edge_count());
+        }
 
         // Acquire the communication channels between the nodes from the edges
         let channels: Vec<(Vec<_>, Vec<_>)> = self.graph.node_indices()
@@ -111,40 +670,218 @@ impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
 
         // Initialize some references for the threads
         let node_count = self.graph.node_count();
-        let stop_count = Arc::new(AtomicU32::new(0));
+        let round_limit = self.round_limit;
+        let (bandwidth, message_size) = (self.bandwidth, self.message_size);
+        let jitter = self.jitter;
+        let crash_rounds = self.crashes.rounds(node_count);
+        let neighbor_crashes: Vec<Vec<_>> = self.ports()
+            .into_iter()
+            .map(|n| n.into_iter().map(|v| crash_rounds[v]).collect())
+            .collect();
+        let (crash_rounds, neighbor_crashes) = (&crash_rounds, &neighbor_crashes);
+
+        // Byzantine nodes never need to stop, and crashed nodes only until their crash
+        let termination = Termination::new(crash_rounds
+            .iter()
+            .zip(&self.byzantine)
+            .map(|(c, b)| if *b { Some(0) } else { c.map(|c| c - 1) }));
+        let termination = &termination;
+
+        // Every node injects the message faults into the messages it sends with its own generator
+        let mut fault_rng = StdRng::seed_from_u64(self.message_faults.seed);
+        let injectors: Vec<_> = self.ports()
+            .iter()
+            .enumerate()
+            .map(|(i, n)| Injector::new(&self.message_faults, i, n, self.mutators, fault_rng.gen()))
+            .map(|injector| self.message_faults.any().then_some(injector))
+            .collect();
+
+        // The views of the adversary are composed from the states published by all nodes
+        let network = self.ports();
+        let board = self.byzantine.contains(&true).then(|| Board::new(node_count));
+        let (byzantine, adversary, network, board) = (&self.byzantine, self.adversary.as_ref(), &network, board.as_ref());
+        let algorithm = &self.algorithm;
+        let mut stats = Vec::with_capacity(node_count);
+
+        // Compose the observers for this run, including the internal ones for tracing and the UI
+        let tracer = self.tracing.then(|| Tracer::new(node_count));
+        let mut observers: Vec<&dyn Observer<S, M>> = self.observers.iter().map(|o| o.as_ref()).collect();
+        observers.extend(tracer.as_ref().map(|t| t as &dyn Observer<S, M>));
+        observers.extend(monitor.map(|m| m as &dyn Observer<S, M>));
+        let observers = &observers;
 
         // A thread scope allows for spawning a set of threads and waiting for them to finish
         thread::scope(|s| {
             // Compose the necessary data for a single node thread. The "weight" of a node is the
             // payload it carries, in our case that is an instance of the state as defined by the
-            // algorithm to run.
-            self.graph
+            // algorithm to run. With jitter, every node gets its own generator for the delays.
+            let mut rng = jitter.map(|j| StdRng::seed_from_u64(j.seed));
+            let mut nodes: Vec<_> = self.graph
                 .node_weights_mut()
-                .zip(channels.into_iter())
+                .zip(channels)
                 .enumerate()
-                .for_each(|(i, (state, (senders, receivers)))| {
-                    let stop_atomic = Arc::clone(&stop_count);
-                    let deadline = Instant::now() + self.timeout;
+                .zip(injectors)
+                .map(|((i, node), injector)| (i, node, rng.as_mut().map(|r| StdRng::seed_from_u64(r.gen())), injector))
+                .collect();
+
+            // (Optional) start the node threads in a random order
+            if let Some(r) = &mut rng {
+                nodes.shuffle(r);
+            }
+
+            let mut handles: Vec<_> = nodes
+                .into_iter()
+                .map(|(i, (state, (senders, receivers)), mut delays, mut injector)| {
+                    let (crash, neighbor_crashes) = (crash_rounds[i], &neighbor_crashes[i]);
+                    let is_byzantine = byzantine[i];
+                    let mut deadline = Instant::now() + self.timeout;
 
                     // Spawn the node thread
-                    s.spawn(move || {
+                    (i, s.spawn(move || {
                         // Track the stopping state for detecting invalid transitions after stopping
                         let mut stopping_state: Option<S> = None;
                         let mut iterations = 0;
+                        let mut stopped = None;
+                        let (mut sent, mut noops, mut bits) = (0, 0, 0);
+                        let mut panicked = None;
+                        let mut crashed = None;
+                        let mut faults = FaultCounts::default();
+
+                        // (Optional) sleep for a random duration to perturb the scheduling
+                        let mut delay = || if let (Some(j), Some(r)) = (jitter, &mut delays) {
+                            thread::sleep(Duration::from_micros(r.gen_range(0..=j.max.as_micros() as u64)));
+                        };
 
                         loop {
-                            // Send messages based on the current state to all neighbors
+                            // (Optional) wait for the monitor to allow starting the round, the
+                            // time spent paused does not count towards the timeout
+                            if let Some(m) = monitor {
+                                deadline += m.wait(iterations);
+                            }
+
+                            let round = iterations + 1;
+
+                            // (Optional) publish the state for the views of the adversary, also
+                            // before crashing so that every node has published some state
+                            if let Some(b) = board {
+                                b.publish(i, state);
+                            }
+
+                            // (Optional) crash at the beginning of the scheduled round, a crashed
+                            // node no longer needs to stop for the simulation to finish
+                            if crash == Some(round) {
+                                observers.iter().for_each(|o| o.crashed(i, round));
+                                crashed = Some(round);
+                                break;
+                            }
+
+                            // Crashed neighbors are neither sent to nor received from
+                            let live: Vec<_> = neighbor_crashes.iter().map(|c| c.is_none_or(|c| round < c)).collect();
+                            if stopped.is_some() && !live.contains(&true) {
+                                break; // The state of an isolated stopped node can no longer change
+                            }
+
+                            observers.iter().for_each(|o| o.round_start(i, round, state));
+
+                            // Byzantine nodes wait for the states of all nodes at the beginning of
+                            // the round, which the adversary bases the messages on
+                            let states = match board.filter(|_| is_byzantine).map(|b| b.view(round, deadline)) {
+                                Some(None) => {
+                                    observers.iter().for_each(|o| o.timeout(i, round));
+                                    if monitor.is_none() {
+                                        eprintln!("Thread {i}: timeout!");
+                                    }
+
+                                    break;
+                                }
+                                Some(states) => states,
+                                None => None,
+                            };
+
+                            // Compute the messages to send based on the current state, a panic
+                            // of the algorithm makes the node leave the simulation. The adversary
+                            // may leave ports without a message.
+                            let messages = panic::catch_unwind(AssertUnwindSafe(|| {
+                                let mut outbox = Outbox::new(senders.len());
+                                match &states {
+                                    Some(states) => {
+                                        adversary.send(&View { node: i, round, states, network, byzantine }, &mut outbox);
+                                        outbox.into_partial()
+                                    }
+                                    None => {
+                                        algorithm.send(state, &mut outbox);
+                                        outbox.into_messages().into_iter().map(Some).collect()
+                                    }
+                                }
+                            }));
+
+                            let mut messages = match messages {
+                                Ok(m) => m,
+                                Err(e) => {
+                                    panicked = Some(NodePanic::new(i, round, Call::Send, state, Vec::new(), e));
+                                    break;
+                                }
+                            };
+
+                            // (Optional) enforce the CONGEST bandwidth limit
+                            if let (Some(limit), Some(size)) = (bandwidth, message_size) {
+                                if let Some(m) = messages.iter().flatten().find(|m| size(m) > limit) {
+                                    let message = format!("message {m:?} exceeds the bandwidth of {limit} bits");
+                                    panicked = Some(NodePanic::new(i, round, Call::Send, state, Vec::new(), Box::new(message)));
+                                    break;
+                                }
+                            }
+
+                            // Compute the statistics before the messages are moved into the channels
+                            let delivered = || messages.iter().zip(&live).filter(|(_, l)| **l).filter_map(|(m, _)| m.as_ref());
+                            let round_sent = delivered().count() as u64;
+                            let round_noops = delivered().filter(|m| m.is_noop()).count() as u64;
+                            let round_bits: u64 = message_size.map_or(0, |size| delivered()
+                                .filter(|m| !m.is_noop())
+                                .map(|m| size(m) as u64)
+                                .sum());
+
+                            for (port, m) in messages.iter().enumerate().filter(|(p, _)| live[*p]) {
+                                let Some(m) = m else {
+                                    continue;
+                                };
+
+                                observers.iter().for_each(|o| o.message_sent(i, round, port, m));
+                            }
+
+                            // (Optional) substitute the faulty messages, lost messages are sent as
+                            // `None` for keeping the rounds in lock-step
+                            let mut round_faults = FaultCounts::default();
+                            if let Some(injector) = &mut injector {
+                                for (port, fault) in injector.apply(round, &mut messages).into_iter().filter(|(p, _)| live[*p]) {
+                                    observers.iter().for_each(|o| o.message_faulted(i, round, port, fault));
+                                    round_faults += fault;
+                                }
+                            }
+
+                            // Send the messages to all live neighbors
+                            delay();
                             let result = senders
                                 .iter()
-                                .zip(A::send(&state))
-                                .map(|(s, m)| s.send_deadline(m, deadline))
-                                .collect::<Result<(), _>>().err();
+                                .zip(messages)
+                                .zip(&live)
+                                .filter(|(_, l)| **l)
+                                .try_for_each(|((s, m), _)| s.send_deadline(m, deadline))
+                                .err();
 
                             match result {
-                                None => {}
+                                None => {
+                                    sent += round_sent;
+                                    faults += round_faults;
+                                    noops += round_noops;
+                                    bits += round_bits;
+                                }
                                 Some(e) => {
                                     if let SendTimeoutError::Timeout(_) = e {
-                                        eprintln!("Thread {i}: send timeout!")
+                                        observers.iter().for_each(|o| o.timeout(i, round));
+                                        if monitor.is_none() {
+                                            eprintln!("Thread {i}: send timeout!");
+                                        }
                                     }
 
                                     // Message channel was closed, execution is finished
@@ -153,16 +890,54 @@ impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
                             }
 
                             // Receive messages from all neighbors
+                            delay();
                             let messages = receivers
                                 .iter()
-                                .map(|r| r.recv_deadline(deadline))
+                                .zip(&live)
+                                .map(|(r, l)| match l {
+                                    true => r.recv_deadline(deadline).map(|m| m.or_else(M::absent)),
+                                    false => Ok(M::absent()),
+                                })
                                 .collect::<Result<Vec<_>, _>>();
 
                             match messages {
-                                Ok(m) => *state = A::receive(&state, m.into_iter()),
+                                Ok(m) => {
+                                    for (port, m) in m.iter().enumerate() {
+                                        if let Some(m) = m {
+                                            observers.iter().for_each(|o| o.message_received(i, round, port, m));
+                                        }
+                                    }
+
+                                    // The messages are formatted beforehand, as they are consumed
+                                    let formatted = m.iter().map(|m| m.as_ref().map_or("nothing".into(), |m| format!("{m:?}"))).collect();
+                                    let m: Vec<_> = m.into_iter().flatten().collect();
+                                    let next = match panic::catch_unwind(AssertUnwindSafe(|| algorithm.receive(state, m.into_iter()))) {
+                                        Ok(next) => next,
+                                        Err(e) => {
+                                            panicked = Some(NodePanic::new(i, round, Call::Receive, state, formatted, e));
+                                            break;
+                                        }
+                                    };
+
+                                    // Invalid stopping state transition detection
+                                    if stopping_state.as_ref().is_some_and(|s| next != *s) {
+                                        let message = format!("detected post-stop state transition to {next:?}");
+                                        panicked = Some(NodePanic::new(i, round, Call::Receive, state, formatted, Box::new(message)));
+                                        break;
+                                    }
+
+                                    if next != *state {
+                                        observers.iter().for_each(|o| o.state_transition(i, round, state, &next));
+                                    }
+
+                                    *state = next;
+                                }
                                 Err(e) => {
                                     if let RecvTimeoutError::Timeout = e {
-                                        println!("Thread {i}: receive timeout!")
+                                        observers.iter().for_each(|o| o.timeout(i, round));
+                                        if monitor.is_none() {
+                                            eprintln!("Thread {i}: receive timeout!");
+                                        }
                                     }
 
                                     // Message channel was closed, execution is finished
@@ -170,72 +945,292 @@ impl<A: DistributedAlgorithm<S, M>, S: State, M: Message> DaSimulator<A, S, M> {
                                 }
                             }
 
-                            if let Some(s) = &stopping_state {
-                                // Invalid stopping state transition detection
-                                assert_eq!(state, s, "detected post-stop state transition");
-                            } else if state.is_output() {
-                                stopping_state = Some(state.clone());
+                            // The communication round is now complete
+                            iterations += 1;
 
-                                // If the node reached a stopping state add
-                                // it to the atomic counter of stopped nodes
-                                stop_atomic.fetch_add(1, Ordering::Relaxed);
+                            if stopping_state.is_none() && state.is_output() && !is_byzantine {
+                                stopping_state = Some(state.clone());
+                                stopped = Some(round);
+                                observers.iter().for_each(|o| o.output_reached(i, round, state));
                             }
 
+                            observers.iter().for_each(|o| o.round_end(i, round, state));
+
                             // If all nodes have reached a stopping state, stop the simulation
-                            if stop_atomic.load(Ordering::Relaxed) >= node_count as u32 {
+                            termination.complete(i, round, stopped.is_some());
+                            if termination.terminates(i, round, deadline) {
                                 break;
                             }
 
                             // (Optional) communication round limiting
-                            iterations += 1;
-                            if round_limit > 0 && iterations >= round_limit {
+                            if round_limit.is_some_and(|l| iterations >= l) {
+                                break;
+                            }
+
+                            // Sending and receiving succeed regardless of the timeout as long as
+                            // the neighbors keep up, so it is checked explicitly for livelocks and
+                            // for nodes whose neighbors have all crashed
+                            if Instant::now() >= deadline {
+                                observers.iter().for_each(|o| o.timeout(i, round));
+                                if monitor.is_none() {
+                                    eprintln!("Thread {i}: timeout!");
+                                }
+
                                 break;
                             }
                         }
 
                         // Close channels to notify neighbor nodes of completion
-                        senders.into_iter().for_each(|s| drop(s));
-                        receivers.into_iter().for_each(|s| drop(s));
-                    });
-                });
+                        termination.leave(i);
+                        if let Some(b) = board {
+                            b.finish(i);
+                        }
+
+                        senders.into_iter().for_each(drop);
+                        receivers.into_iter().for_each(drop);
+                        NodeStats { rounds: iterations, stopped, sent, noops, bits, panic: panicked, crashed, faults }
+                    }))
+                })
+                .collect();
+
+            // Collect the statistics in order of node indices
+            handles.sort_by_key(|(i, _)| *i);
+            stats.extend(handles.into_iter().map(|(_, h)| h.join().expect("node thread panicked")));
         });
 
-        let unfinished = self.graph.node_weights().filter(|s| !s.is_output()).count();
-        if unfinished > 0 {
+        // Report the earliest panic, the neighbors may panic later due to the missing node
+        let panic = stats.iter_mut().filter_map(|s| s.panic.take()).min_by_key(|p| (p.round, p.node));
+        let unfinished = self.graph.node_weights().zip(&self.byzantine).filter(|(s, b)| !s.is_output() || **b).count();
+
+        let result = RunResult {
+            nodes: node_count,
+            stopped: node_count - unfinished,
+            rounds: stats.iter().map(|s| s.rounds).max().unwrap_or(0),
+            messages: stats.iter().map(|s| s.sent).sum(),
+            edges: self.graph.edge_count(),
+            stopping_rounds: stats.iter().map(|s| s.stopped).collect(),
+            noops: stats.iter().map(|s| s.noops).sum(),
+            bits: message_size.map(|_| stats.iter().map(|s| s.bits).sum()),
+            panic,
+            crashes: stats.iter().map(|s| s.crashed).collect(),
+            faults: stats.iter().map(|s| s.faults).sum(),
+            byzantine: self.byzantine.clone(),
+        };
+
+        observers.iter().for_each(|o| o.finished(&result));
+        if let Some(t) = tracer {
+            self.trace = t.into_trace();
+        }
+
+        if monitor.is_none() {
+            self.report(&result);
+        }
+
+        result
+    }
+
+    /// Report the outcome of the given run of the simulation to stderr
+    fn report(&self, result: &RunResult) {
+        let running = self.graph
+            .node_weights()
+            .zip(&result.crashes)
+            .zip(&self.byzantine)
+            .filter(|((s, c), b)| !s.is_output() && c.is_none() && !**b)
+            .count();
+        if let Some(p) = &result.panic {
+            eprintln!("\nSimulation FAILED! The algorithm panicked, aborting:\n{p}");
+        } else if running > 0 {
             eprintln!(
                 "\nSimulation FAILED! Timeout reached with {} node(s) still running, states in the\n\
                 resulting network are NOT final! Hint: check for deadlocks or increase the timeout.",
-                unfinished
+                running
             )
+        } else if result.stopped < result.nodes {
+            eprintln!("\nSimulation successful! All honest nodes reached stopping states or crashed.");
         } else {
-            println!("\nSimulation successful! All nodes reached stopping states.");
+            eprintln!("\nSimulation successful! All nodes reached stopping states.");
         }
     }
 
-    /// Output the network in the [Graphviz DOT format](https://graphviz.org/doc/info/lang.html)
-    pub fn print(&self) {
-        // Function for resolving the port number of an edge
+    /// Output the network in the [Graphviz DOT format](https://graphviz.org/doc/info/lang.html).
+    /// Nodes and edges are styled according to the `dot_attributes` and `highlighted_ports` of the
+    /// node states, and the node index is shown next to the state if `node_ids` is set.
+    pub fn print(&self, node_ids: bool) {
+        let states: Vec<_> = self.graph.node_weights().collect();
+        println!("\n{}", self.dot(&self.frame(&states, None), node_ids));
+    }
+
+    /// Serialize the network with the current states into JSON. The result contains the nodes with
+    /// their states, stopping status and local outputs (see `State::output`) as well as the edges
+    /// with their (one-based) port numbers.
+    pub fn to_json(&self) -> serde_json::Value {
+        let states: Vec<_> = self.graph.node_weights().collect();
+        let frame = self.frame(&states, None);
+
+        json!({
+            "algorithm": self.algorithm.name(),
+            "nodes": frame.nodes.iter().zip(&states).enumerate().map(|(i, (n, s))| json!({
+                "id": i,
+                "state": n.label,
+                "stopped": s.is_output(),
+                "output": s.output(),
+            })).collect::<Vec<_>>(),
+            "edges": frame.edges.iter().map(|e| json!({
+                "nodes": [e.nodes.0, e.nodes.1],
+                "ports": [e.ports.0 + 1, e.ports.1 + 1],
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write one DOT file per communication round of the previous run into the given directory,
+    /// named `frame_0000.dot`, `frame_0001.dot`, etc. Each frame shows the states of the nodes at
+    /// the beginning of the round, and, if `message_labels` is set, the messages sent over each
+    /// edge in that round. The last frame shows the final states. Requires the trace of the run to
+    /// be recorded using `record_trace`, fails otherwise. Returns the number of frames written.
+    ///
+    /// The frames can be rendered with e.g. `dot -Tpng -O frames/*.dot` and combined into an
+    /// animation with e.g. `convert -delay 100 frames/*.png animation.gif`.
+    pub fn write_frames(&self, dir: impl AsRef<Path>, message_labels: bool) -> io::Result<usize> {
+        if self.trace.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no trace recorded, enable it using `record_trace`"));
+        }
+
+        fs::create_dir_all(&dir)?;
+
+        let mut frames = self.frames();
+        for (round, frame) in frames.iter_mut().enumerate() {
+            if !message_labels {
+                frame.edges.iter_mut().for_each(|e| e.messages = (None, None));
+            }
+
+            fs::write(dir.as_ref().join(format!("frame_{:04}.dot", round)), self.dot(frame, true))?;
+        }
+
+        Ok(frames.len())
+    }
+
+    /// Write a self-contained HTML visualization of the network into the given file. If the trace
+    /// of the previous run has been recorded using `record_trace`, the visualization allows
+    /// scrubbing through the communication rounds, otherwise it only shows the current states.
+    pub fn write_html(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let title = format!("{} ({} nodes, {} edges)",
+                            self.algorithm.name(), self.graph.node_count(), self.graph.edge_count());
+        fs::write(path, visualizer::render_html(&title, &self.frames())?)
+    }
+
+    /// Compose the frames of the previous run from the recorded trace. Each frame contains the
+    /// states of the nodes at the beginning of a round and the messages sent during it, followed
+    /// by a final frame containing the current states. Nodes that have already finished are shown
+    /// in their final states without sending any messages.
+    fn frames(&self) -> Vec<Frame> {
+        let rounds = self.trace.iter().map(|t| t.len()).max().unwrap_or(0);
+        let mut frames: Vec<_> = (0..rounds)
+            .map(|round| {
+                let states: Vec<_> = self.trace
+                    .iter()
+                    .zip(self.graph.node_weights())
+                    .map(|(t, s)| t.get(round).map_or(s, |r| &r.state))
+                    .collect();
+
+                let messages: Vec<_> = self.trace
+                    .iter()
+                    .map(|t| t.get(round).map_or(&[][..], |r| &r.messages[..]))
+                    .collect();
+
+                self.frame(&states, Some(&messages))
+            })
+            .collect();
+
+        let states: Vec<_> = self.graph.node_weights().collect();
+        frames.push(self.frame(&states, None));
+        frames
+    }
+
+    /// Compose a renderable frame of the network with the given node states and optionally the
+    /// given formatted messages sent to each port of each node
+    fn frame(&self, states: &[&S], messages: Option<&[&[Option<String>]]>) -> Frame {
+        // Function for resolving the (zero-based) port number of an edge
         let pn = |er: EdgeReference<Edge<M>>, source|
             self.edges(if source { er.source() } else { er.target() })
                 .into_iter()
-                .enumerate()
-                .find(|(_, e)| e == &er)
-                .map(|(i, _)| i + 1)
+                .position(|e| e == er)
                 .expect("inconsistent edge");
 
-        // Helper for formatting an edge with port numbers
-        let edge_format = |_, er|
-            format!("taillabel = \"{}\" headlabel = \"{}\" ", pn(er, true), pn(er, false));
+        let nodes = states
+            .iter()
+            .map(|s| NodeView {
+                label: format!("{:?}", s),
+                attributes: s.dot_attributes(),
+            })
+            .collect();
+
+        let edges = self.graph
+            .edge_references()
+            .map(|er| {
+                let (a, b) = (er.source().index(), er.target().index());
+                let (pa, pb) = (pn(er, true), pn(er, false));
+                let message = |node: usize, port| messages
+                    .and_then(|m| m[node].get(port))
+                    .cloned()
+                    .flatten();
+
+                EdgeView {
+                    nodes: (a, b),
+                    ports: (pa, pb),
+                    highlighted: states[a].highlighted_ports().contains(&(pa as u32)) ||
+                        states[b].highlighted_ports().contains(&(pb as u32)),
+                    messages: (message(a, pa), message(b, pb)),
+                }
+            })
+            .collect();
+
+        Frame { nodes, edges }
+    }
+
+    /// Serialize the network into the DOT format as described by the given frame
+    fn dot(&self, frame: &Frame, node_ids: bool) -> String {
+        // Helper for formatting an edge with port numbers and optionally messages
+        let edge_format = |_, er: EdgeReference<Edge<M>>| {
+            let edge = &frame.edges[er.id().index()];
+            let messages = [(edge.nodes.0, &edge.messages.0), (edge.nodes.1, &edge.messages.1)]
+                .into_iter()
+                .filter_map(|(i, m)| m.as_ref().map(|m| format!("{}: {}", i, m)))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!(
+                "taillabel = \"{}\" headlabel = \"{}\" {}{}",
+                edge.ports.0 + 1,
+                edge.ports.1 + 1,
+                if messages.is_empty() { String::new() } else { format!("label = \"{}\" ", escape(&messages)) },
+                if edge.highlighted { "color = \"red\" penwidth = 3 " } else { "" },
+            )
+        };
+
+        // Helper for formatting a node with its state and optionally its index
+        let node_format = |_, (i, _): (NodeIndex, &S)| {
+            let node = &frame.nodes[i.index()];
+            let label = match node_ids {
+                true => format!("{}: {}", i.index(), node.label),
+                false => node.label.clone(),
+            };
+
+            format!("label = \"{}\" {}", escape(&label), node.attributes)
+        };
 
         // Serialize the internal graph to DOT format
         let dot = Dot::with_attr_getters(
             &self.graph,
-            &[Config::EdgeNoLabel],
+            &[Config::EdgeNoLabel, Config::NodeNoLabel],
             &edge_format,
-            &|_, _| String::new(),
+            &node_format,
         );
 
-        // Print the serialization
-        println!("\n{:?}", dot);
+        format!("{:?}", dot)
     }
 }
+
+/// Escape a string for use as a quoted ID in the DOT format
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
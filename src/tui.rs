@@ -0,0 +1,289 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use crossterm::event::{Event as TermEvent, KeyCode};
+use crate::observer::Observer;
+use crate::simulator::RunResult;
+use crate::types::*;
+
+/// Progress event reported by a node thread to the terminal UI
+pub enum Event {
+    /// A node completed the given number of communication rounds
+    Round {
+        node: usize,
+        round: u32,
+        state: String,
+        stopped: bool,
+        received: Vec<String>,
+    },
+    /// A node hit the timeout while sending or receiving
+    Timeout { node: usize },
+    /// A node crashed, see `faults::Crashes`
+    Crash { node: usize },
+    /// The simulation has finished
+    Finished,
+}
+
+/// Execution control shared between the node threads and the terminal UI. Allows the UI to pause
+/// the simulation at a round boundary, step through it one round at a time, and resume it. The
+/// progress of the nodes is reported to the UI by observing the simulation.
+pub struct Monitor {
+    gate: Mutex<Gate>,
+    condvar: Condvar,
+    events: Sender<Event>,
+    // Formatted messages received by each node in its ongoing round
+    received: Mutex<HashMap<usize, Vec<String>>>,
+}
+
+/// Shared state for pausing the node threads at round boundaries
+#[derive(Default)]
+struct Gate {
+    // Round that the node threads are not allowed to start, `None` if running freely
+    limit: Option<u32>,
+    // The most advanced round started by any node thread
+    started: u32,
+}
+
+impl Monitor {
+    /// Create a new monitor, returning it together with the receiving end for the node events
+    pub fn new() -> (Self, Receiver<Event>) {
+        let (events, receiver) = unbounded();
+        let monitor = Self {
+            gate: Mutex::default(),
+            condvar: Condvar::new(),
+            events,
+            received: Mutex::default(),
+        };
+
+        (monitor, receiver)
+    }
+
+    /// Called by node threads before starting the given round, blocks while the simulation is
+    /// paused before that round. Returns the time spent waiting, which should not count towards
+    /// the timeout of the node.
+    pub fn wait(&self, round: u32) -> Duration {
+        let start = Instant::now();
+        let gate = self.gate.lock().unwrap();
+        let mut gate = self.condvar
+            .wait_while(gate, |g| g.limit.is_some_and(|l| round >= l))
+            .unwrap();
+
+        gate.started = gate.started.max(round);
+        start.elapsed()
+    }
+
+    /// Report an event to the terminal UI
+    fn report(&self, event: Event) {
+        // The UI may have been closed already, in which case the events are discarded
+        let _ = self.events.send(event);
+    }
+
+    /// Pause the simulation after the most advanced round that has already been started. Pausing
+    /// any earlier would leave the nodes in that round waiting for messages from their neighbors.
+    fn pause(&self) {
+        let mut gate = self.gate.lock().unwrap();
+        gate.limit = Some(gate.started + 1);
+    }
+
+    /// Allow the simulation to proceed by one more round if paused
+    fn step(&self) {
+        if let Some(l) = self.gate.lock().unwrap().limit.as_mut() {
+            *l += 1;
+        }
+
+        self.condvar.notify_all();
+    }
+
+    /// Resume the simulation if paused
+    fn resume(&self) {
+        self.gate.lock().unwrap().limit = None;
+        self.condvar.notify_all();
+    }
+
+    /// Determine the number of rounds after which the simulation is paused, if any
+    fn paused(&self) -> Option<u32> {
+        self.gate.lock().unwrap().limit
+    }
+}
+
+impl<S: State, M: Message> Observer<S, M> for Monitor {
+    fn message_received(&self, node: usize, _: u32, _: usize, message: &M) {
+        self.received.lock().unwrap().entry(node).or_default().push(format!("{:?}", message));
+    }
+
+    fn round_end(&self, node: usize, round: u32, state: &S) {
+        self.report(Event::Round {
+            node,
+            round,
+            state: format!("{:?}", state),
+            stopped: state.is_output(),
+            received: self.received.lock().unwrap().remove(&node).unwrap_or_default(),
+        });
+    }
+
+    fn timeout(&self, node: usize, _: u32) {
+        self.report(Event::Timeout { node });
+    }
+
+    fn crashed(&self, node: usize, _: u32) {
+        self.report(Event::Crash { node });
+    }
+
+    fn finished(&self, _: &RunResult) {
+        self.report(Event::Finished);
+    }
+}
+
+/// Latest known progress of a single node
+#[derive(Clone, Default)]
+struct Row {
+    round: Option<u32>,
+    state: String,
+    stopped: bool,
+    received: Vec<String>,
+    timeout: bool,
+    crashed: bool,
+}
+
+/// Show the live terminal UI for a simulation with the given title and node count until the
+/// simulation finishes (or the user quits). Keys: `space` pauses/resumes, `s` steps a single round
+/// when paused, arrow keys and page up/down scroll the node table and `q` quits the UI while
+/// letting the simulation run to completion.
+pub fn show(title: &str, node_count: usize, monitor: &Monitor, events: Receiver<Event>) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = ui_loop(&mut out, title, node_count, monitor, events);
+
+    // Never leave the simulation paused without a UI to resume it
+    monitor.resume();
+    execute!(out, terminal::LeaveAlternateScreen, cursor::Show)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+/// Main loop of the terminal UI, processes node events and key presses and redraws the screen
+fn ui_loop(
+    out: &mut impl Write,
+    title: &str,
+    node_count: usize,
+    monitor: &Monitor,
+    events: Receiver<Event>,
+) -> io::Result<()> {
+    let mut rows = vec![Row::default(); node_count];
+    let mut offset: usize = 0;
+    let mut finished = false;
+
+    loop {
+        // Process all pending events from the node threads
+        for e in events.try_iter() {
+            match e {
+                Event::Round { node, round, state, stopped, received } => {
+                    rows[node] = Row { round: Some(round), state, stopped, received, ..rows[node].clone() };
+                }
+                Event::Timeout { node } => rows[node].timeout = true,
+                Event::Crash { node } => rows[node].crashed = true,
+                Event::Finished => finished = true,
+            }
+        }
+
+        let (_, height) = terminal::size()?;
+        let visible = (height as usize).saturating_sub(5);
+
+        if event::poll(Duration::from_millis(50))? {
+            if let TermEvent::Key(key) = event::read()? {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => match monitor.paused() {
+                        Some(_) => monitor.resume(),
+                        None => monitor.pause(),
+                    },
+                    KeyCode::Char('s') => monitor.step(),
+                    KeyCode::Up => offset = offset.saturating_sub(1),
+                    KeyCode::Down => offset += 1,
+                    KeyCode::PageUp => offset = offset.saturating_sub(visible),
+                    KeyCode::PageDown => offset += visible,
+                    _ => {}
+                }
+            }
+        }
+
+        offset = offset.min(node_count.saturating_sub(visible));
+        draw(out, title, &rows, offset, visible, monitor.paused(), finished)?;
+
+        if finished {
+            // Keep the final state on screen until the user quits
+            loop {
+                if let TermEvent::Key(key) = event::read()? {
+                    if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw the status header and the node table starting from the given row offset
+fn draw(
+    out: &mut impl Write,
+    title: &str,
+    rows: &[Row],
+    offset: usize,
+    visible: usize,
+    paused: Option<u32>,
+    finished: bool,
+) -> io::Result<()> {
+    let stopped = rows.iter().filter(|r| r.stopped).count();
+    let timeouts = rows.iter().filter(|r| r.timeout).count();
+    let crashed = rows.iter().filter(|r| r.crashed && !r.stopped).count();
+    let status = match (finished, paused) {
+        (true, _) => "FINISHED - press q to exit".to_string(),
+        (false, Some(r)) => format!("PAUSED after round {r} - space: resume, s: step, q: quit"),
+        (false, None) => "RUNNING - space: pause, q: quit".to_string(),
+    };
+
+    let (width, _) = terminal::size()?;
+    let header = [
+        title.to_string(),
+        format!("Running: {}  Stopped: {}  Crashed: {}  Timeouts: {}", rows.len() - stopped - crashed, stopped, crashed, timeouts),
+        status,
+        format!("{:>6} {:>6} {:>7}  {:<24} Last received", "Node", "Rounds", "Stopped", "State"),
+    ];
+
+    let table = rows.iter().enumerate().skip(offset).take(visible).map(|(i, r)| format!(
+        "{:>6} {:>6} {:>7}  {:<24} {}",
+        i,
+        r.round.map_or("-".into(), |r| r.to_string()),
+        if r.crashed { "crashed" } else if r.timeout { "timeout" } else if r.stopped { "yes" } else { "no" },
+        r.state,
+        r.received.join(", "),
+    ));
+
+    let mut y = 0;
+    for line in header.into_iter().chain(table) {
+        let line: String = line.chars().take(width as usize).collect();
+        queue!(
+            out,
+            cursor::MoveTo(0, y),
+            style::Print(line),
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+        )?;
+        y += 1;
+    }
+
+    queue!(out, cursor::MoveTo(0, y), terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    out.flush()
+}
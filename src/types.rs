@@ -7,29 +7,90 @@
  */
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use crossbeam_channel::{bounded, Receiver, Sender};
+use serde::Serialize;
+use crate::verifier::{Label, Problem};
 
 /// A `Message` is an object that can be sent over a single edge in the DA state machine
-pub trait Message: fmt::Debug + Send {}
+pub trait Message: fmt::Debug + Send {
+    /// Determines if the message carries no information, i.e. it only exists because the formal
+    /// model requires a message for every port. No-op messages are excluded from the message
+    /// complexity statistics, see `RunResult`.
+    fn is_noop(&self) -> bool {
+        false
+    }
+
+    /// Marker received in place of the messages of a crashed neighbor, a dropped message or a
+    /// message not sent by a Byzantine neighbor, see `faults` and `adversary`. These require a
+    /// marker, as leaving the missing messages out of the received ones would shift the following
+    /// ports.
+    fn absent() -> Option<Self> where Self: Sized {
+        None
+    }
+}
+
+/// Local output of a node in a stopping state, labelling either the node itself, its ports or its
+/// incident edges with values of type `L`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output<L> {
+    /// A label for the node, e.g. whether it is part of a vertex cover
+    Node(L),
+    /// A label for each port of the node in order, i.e. for each half-edge, e.g. an orientation
+    Ports(Vec<L>),
+    /// A label for each incident edge by port in order, e.g. whether the edge is part of a
+    /// matching. Both endpoints of an edge must agree on its label.
+    Edges(Vec<L>),
+}
 
 /// A `State` represents a configuration a single node can transition to in the DA state machine
 pub trait State: Clone + fmt::Debug + PartialEq + Send {
+    /// Type of the labels in the local output of a node, see `Output`
+    type Output: Label + Send + Serialize;
+
     /// Determines if the state is a stopping state
     fn is_output(&self) -> bool;
+
+    /// Local output of the node, `None` if the state is not a stopping state
+    fn output(&self) -> Option<Output<Self::Output>>;
+
+    /// Additional [Graphviz attributes](https://graphviz.org/doc/info/attrs.html) for rendering a
+    /// node in this state in the DOT output, e.g. `style = filled fillcolor = "lightblue"`
+    fn dot_attributes(&self) -> String {
+        String::new()
+    }
+
+    /// Zero-based ports whose edges should be highlighted in the DOT output, e.g. the port over
+    /// which a node has been matched
+    fn highlighted_ports(&self) -> Vec<u32> {
+        Vec::new()
+    }
+
+    /// Determines if the node is part of the solution computed by the algorithm, e.g. a vertex
+    /// cover, which is used for measuring the solution quality. `None` if the algorithm does not
+    /// compute such a solution.
+    fn in_solution(&self) -> Option<bool> {
+        None
+    }
 }
 
-/// An `Edge` describes a bidirectional communication channel between two nodes
+/// One end of the communication channel of an edge, see `Edge::endpoint`
+pub type Endpoint<M> = (Sender<Option<M>>, Receiver<Option<M>>);
+
+/// An `Edge` describes a bidirectional communication channel between two nodes. The channel
+/// carries `None` in place of messages lost due to faults, see `faults::MessageFaults`.
 #[derive(Debug)]
 pub struct Edge<M: Message> {
-    channel: RefCell<Option<(Sender<M>, Receiver<M>)>>,
+    channel: RefCell<Option<Endpoint<M>>>,
     connected: RefCell<bool>,
 }
 
 impl<M: Message> Edge<M> {
     /// Acquire one endpoint of the edge, the returned `Sender` and `Receiver` pair can be used to
     /// communicate with the other end
-    pub fn endpoint(&self) -> (Sender<M>, Receiver<M>) {
+    pub fn endpoint(&self) -> Endpoint<M> {
         if let Some((s, r)) = self.channel.take() {
             assert!(!self.connected.replace(true), "attempt to acquire third endpoint for edge");
             return (s, r);
@@ -40,6 +101,12 @@ impl<M: Message> Edge<M> {
         self.channel.replace(Some((s1, r2)));
         (s2, r1)
     }
+
+    /// Disconnect both endpoints of the edge, so that a fresh channel is created for the next run
+    pub fn reset(&self) {
+        self.channel.take();
+        self.connected.replace(false);
+    }
 }
 
 // Manual implementation needed to avoid `Default` dependency on `M`
@@ -62,31 +129,102 @@ impl<M: Message> PartialEq for Edge<M> {
 /// such algorithms operating in the PN model should disregard fields such as `node_id` as a source
 /// of unique identifiers.
 #[allow(unused)]
-pub struct Input {
+pub struct Input<'a> {
     pub node_id: u32,
     pub node_count: u32,
     pub node_degree: u32,
+    /// Local input of the node, such as an initial color or a weight, to be parsed by the algorithm
+    pub local_input: Option<&'a str>,
 }
 
-/// Programmatic representation of the formal definition of a distributed algorithm
-pub trait DistributedAlgorithm<S: State, M: Message> {
-    /// Algorithm-provided iterator type for a stream of messages to send
-    type MsgIter: Iterator<Item=M>;
+/// Local inputs of the nodes by node index, see `Input::local_input`
+pub type Inputs = HashMap<u32, String>;
 
+/// Per-port output buffer of a node for a single communication round, filled in by
+/// `DistributedAlgorithm::send`. Every port must receive exactly one message before the round can
+/// proceed, for example:
+///
+/// ```
+/// use pn_simulator::Outbox;
+///
+/// let mut outbox = Outbox::new(3);
+/// outbox.send(1, "proposal");
+/// outbox.fill("noop"); // All the other ports
+/// assert_eq!(outbox.into_messages(), ["noop", "proposal", "noop"]);
+/// ```
+#[derive(Debug)]
+pub struct Outbox<M> {
+    messages: Vec<Option<M>>,
+}
+
+impl<M> Outbox<M> {
+    /// Create an empty output buffer for a node with the given number of ports
+    pub fn new(ports: usize) -> Self {
+        Self { messages: (0..ports).map(|_| None).collect() }
+    }
+
+    /// Number of ports of the node, i.e. its degree
+    pub fn ports(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Send the given message to the given zero-based port, replacing any message already sent to it
+    pub fn send(&mut self, port: usize, message: M) {
+        assert!(port < self.ports(), "attempt to send to port {} of a node with {} ports", port, self.ports());
+        self.messages[port] = Some(message);
+    }
+
+    /// Send a copy of the given message to every port, replacing any messages already sent
+    pub fn broadcast(&mut self, message: M) where M: Clone {
+        self.messages.iter_mut().for_each(|m| *m = Some(message.clone()));
+    }
+
+    /// Send a copy of the given message to every port that has not been sent a message yet
+    pub fn fill(&mut self, message: M) where M: Clone {
+        self.messages.iter_mut().filter(|m| m.is_none()).for_each(|m| *m = Some(message.clone()));
+    }
+
+    /// Consume the buffer, returning the messages of each port in order. Panics if some port has
+    /// not been sent a message.
+    pub fn into_messages(self) -> Vec<M> {
+        self.messages
+            .into_iter()
+            .enumerate()
+            .map(|(port, m)| m.unwrap_or_else(|| panic!("no message sent to port {port}")))
+            .collect()
+    }
+
+    /// Consume the buffer, returning the messages of each port in order, `None` for the ports
+    /// that have not been sent a message. Only adversaries may leave ports empty, see `Adversary`.
+    pub fn into_partial(self) -> Vec<Option<M>> {
+        self.messages
+    }
+}
+
+/// Programmatic representation of the formal definition of a distributed algorithm. An instance of
+/// the implementing type is shared by all nodes, so it can carry parameters (such as a depth or a
+/// color count) chosen per run, but it must not be used to share any state between the nodes.
+pub trait DistributedAlgorithm<S: State, M: Message>: Sync {
     /// Function to retrieve the name of the algorithm
-    fn name() -> String;
+    fn name(&self) -> String;
+
+    /// Problem solved by the algorithm, used for verifying the outputs after a run. `None` if the
+    /// outputs cannot be verified.
+    fn problem(&self) -> Option<Problem> {
+        None
+    }
 
     /// `init` function of the formal definition of a distributed algorithm. Takes in an input with
     /// graph/node details (but may choose to ignore it), and returns the initial state of a node.
-    fn init(info: &Input) -> S;
+    fn init(&self, info: &Input) -> S;
 
     /// `send` function of the formal definition of a distributed algorithm. Takes in an immutable
-    /// reference to the current state, and must produce an iterator of messages to be sent to each
-    /// port of the node in order.
-    fn send(state: &S) -> Self::MsgIter;
+    /// reference to the current state, and must write a message for each port of the node into the
+    /// given output buffer.
+    fn send(&self, state: &S, outbox: &mut Outbox<M>);
 
     /// `receive` function of the formal definition of a distributed algorithm. Takes in an
     /// immutable reference to the current state as well as an iterator with the messages received
     /// from each port in order, and must produce a new state that the node then transitions to.
-    fn receive(state: &S, messages: impl Iterator<Item=M>) -> S;
+    fn receive(&self, state: &S, messages: impl Iterator<Item=M>) -> S;
 }
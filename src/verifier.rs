@@ -0,0 +1,472 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fmt;
+use std::str::FromStr;
+use crate::optimum;
+use crate::types::Output;
+
+/// Graph problem whose solutions can be verified from the local outputs of the nodes. For example,
+/// checking a colouring of a path of three nodes:
+///
+/// ```
+/// use pn_simulator::Output;
+/// use pn_simulator::verifier::{Problem, Violation};
+///
+/// let ports = [vec![1], vec![0, 2], vec![1]];
+/// let valid = [Some(Output::Node(1)), Some(Output::Node(2)), Some(Output::Node(1))];
+/// assert_eq!(Problem::VertexColoring.verify(&ports, &valid), Ok(()));
+///
+/// let invalid = [Some(Output::Node(1)), Some(Output::Node(1)), None];
+/// assert_eq!(Problem::VertexColoring.verify(&ports, &invalid), Err(vec![
+///     Violation::Node(2, "no output".into()),
+///     Violation::Edge(0, 1, "both endpoints have colour 1".into()),
+/// ]));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Problem {
+    /// Edge outputs marking the matched edges, every edge must have a matched endpoint
+    MaximalMatching,
+    /// Node outputs marking the cover, every edge must have an endpoint in the cover
+    VertexCover,
+    /// Node outputs marking the set, no two adjacent nodes may be in the set
+    IndependentSet,
+    /// Node outputs marking the set, which must be independent and not extendable by any node
+    MaximalIndependentSet,
+    /// Node outputs giving the colours, adjacent nodes must have different colours
+    VertexColoring,
+    /// Edge outputs giving the colours, adjacent edges must have different colours
+    EdgeColoring,
+    /// Node outputs marking the set, every node must be in the set or have a neighbor in it
+    DominatingSet,
+    /// Edge outputs marking the tree edges, which must connect all nodes without cycles
+    SpanningTree,
+}
+
+/// Label of a node, port or edge that can be checked by a verifier. The set problems require
+/// labels that can be interpreted as membership flags.
+pub trait Label: Clone + fmt::Debug + PartialEq {
+    /// Interpret the label as a membership flag, `None` if the label is not a flag
+    fn membership(&self) -> Option<bool> {
+        None
+    }
+}
+
+impl Label for bool {
+    fn membership(&self) -> Option<bool> {
+        Some(*self)
+    }
+}
+
+// Integer and textual labels are typically used for colours
+macro_rules! impl_label {
+    ($($t:ty),*) => { $(impl Label for $t {})* };
+}
+
+impl_label!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, char, String);
+
+/// A constraint of a problem violated by the outputs of the nodes, nodes are given by index
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// The constraint is violated at the given node
+    Node(usize, String),
+    /// The constraint is violated at the edge between the given nodes
+    Edge(usize, usize, String),
+}
+
+/// Network to verify the outputs against, `ports[v][p]` is the neighbor of node `v` behind its
+/// (zero-based) port `p`, see `DaSimulator::ports`
+pub type Ports = [Vec<usize>];
+
+/// Outputs of the nodes by node index, `None` for nodes that did not produce an output
+pub type Outputs<L> = [Option<Output<L>>];
+
+impl FromStr for Problem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "maximal-matching" => Ok(Problem::MaximalMatching),
+            "vertex-cover" => Ok(Problem::VertexCover),
+            "independent-set" => Ok(Problem::IndependentSet),
+            "maximal-independent-set" => Ok(Problem::MaximalIndependentSet),
+            "vertex-coloring" => Ok(Problem::VertexColoring),
+            "edge-coloring" => Ok(Problem::EdgeColoring),
+            "dominating-set" => Ok(Problem::DominatingSet),
+            "spanning-tree" => Ok(Problem::SpanningTree),
+            _ => Err(format!("unknown problem: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MaximalMatching => write!(f, "maximal-matching"),
+            Problem::VertexCover => write!(f, "vertex-cover"),
+            Problem::IndependentSet => write!(f, "independent-set"),
+            Problem::MaximalIndependentSet => write!(f, "maximal-independent-set"),
+            Problem::VertexColoring => write!(f, "vertex-coloring"),
+            Problem::EdgeColoring => write!(f, "edge-coloring"),
+            Problem::DominatingSet => write!(f, "dominating-set"),
+            Problem::SpanningTree => write!(f, "spanning-tree"),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Node(v, reason) => write!(f, "node {v}: {reason}"),
+            Violation::Edge(u, v, reason) => write!(f, "edge {u}-{v}: {reason}"),
+        }
+    }
+}
+
+impl Problem {
+    /// Verify that the given outputs form a valid solution to the problem in the given network,
+    /// returning all the violations found otherwise
+    pub fn verify<L: Label>(&self, ports: &Ports, outputs: &Outputs<L>) -> Result<(), Vec<Violation>> {
+        let violations = match self {
+            Problem::MaximalMatching => maximal_matching(ports, outputs),
+            Problem::VertexCover => vertex_cover(ports, outputs),
+            Problem::IndependentSet => independent_set(ports, outputs),
+            Problem::MaximalIndependentSet => maximal_independent_set(ports, outputs),
+            Problem::VertexColoring => vertex_coloring(ports, outputs),
+            Problem::EdgeColoring => edge_coloring(ports, outputs),
+            Problem::DominatingSet => dominating_set(ports, outputs),
+            Problem::SpanningTree => spanning_tree(ports, outputs),
+        };
+
+        match violations.is_empty() {
+            true => Ok(()),
+            false => Err(violations),
+        }
+    }
+
+    /// Whether the objective of the problem is maximized, e.g. the size of a matching
+    pub fn maximize(&self) -> bool {
+        matches!(self, Problem::MaximalMatching)
+    }
+
+    /// Objective value of the solution given by the outputs, i.e. the number of nodes in the set,
+    /// the number of edges in the matching or the number of colours. `None` if the problem is not
+    /// an optimization problem or some node has no output of the expected kind. The solution
+    /// should be verified first, the value of an invalid solution is meaningless.
+    pub fn value<L: Label>(&self, outputs: &Outputs<L>) -> Option<usize> {
+        match self {
+            Problem::VertexCover | Problem::DominatingSet => outputs
+                .iter()
+                .map(|o| match o {
+                    Some(Output::Node(l)) => l.membership(),
+                    _ => None,
+                })
+                .map(|f| f.map(usize::from))
+                .sum(),
+            // Both endpoints of a matched edge mark it
+            Problem::MaximalMatching => outputs
+                .iter()
+                .map(|o| match o {
+                    Some(Output::Edges(l)) => l.iter().map(|l| l.membership().map(usize::from)).sum::<Option<usize>>(),
+                    _ => None,
+                })
+                .sum::<Option<usize>>()
+                .map(|n| n / 2),
+            Problem::VertexColoring => {
+                let mut colors: Vec<&L> = Vec::new();
+                for o in outputs {
+                    match o {
+                        Some(Output::Node(l)) if !colors.contains(&l) => colors.push(l),
+                        Some(Output::Node(_)) => (),
+                        _ => return None,
+                    }
+                }
+                Some(colors.len())
+            }
+            _ => None,
+        }
+    }
+
+    /// Exact optimal objective value in the given network, see `value`. `None` if the problem is
+    /// not an optimization problem or the network has more than `optimum::MAX_NODES` nodes.
+    pub fn optimum(&self, ports: &Ports) -> Option<usize> {
+        if ports.len() > optimum::MAX_NODES {
+            return None;
+        }
+
+        match self {
+            Problem::VertexCover => Some(optimum::min_vertex_cover(ports)),
+            Problem::MaximalMatching => Some(optimum::max_matching(ports)),
+            Problem::DominatingSet => Some(optimum::min_dominating_set(ports)),
+            Problem::VertexColoring => Some(optimum::chromatic_number(ports)),
+            _ => None,
+        }
+    }
+}
+
+/// Iterate over the edges of the network as `(u, v)` pairs with `u < v`
+fn edges(ports: &Ports) -> impl Iterator<Item=(usize, usize)> + '_ {
+    ports
+        .iter()
+        .enumerate()
+        .flat_map(|(u, n)| n.iter().filter(move |v| u < **v).map(move |v| (u, *v)))
+}
+
+/// Extract the node labels of the outputs, reporting nodes with a missing or different output
+fn node_labels<'a, L: Label>(outputs: &'a Outputs<L>, violations: &mut Vec<Violation>) -> Vec<Option<&'a L>> {
+    outputs
+        .iter()
+        .enumerate()
+        .map(|(v, o)| match o {
+            Some(Output::Node(l)) => Some(l),
+            Some(o) => {
+                violations.push(Violation::Node(v, format!("expected a node output, got {o:?}")));
+                None
+            }
+            None => {
+                violations.push(Violation::Node(v, "no output".into()));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Extract the node labels of the outputs as membership flags
+fn node_flags<L: Label>(outputs: &Outputs<L>, violations: &mut Vec<Violation>) -> Vec<Option<bool>> {
+    node_labels(outputs, violations)
+        .into_iter()
+        .enumerate()
+        .map(|(v, l)| l.and_then(|l| {
+            let flag = l.membership();
+            if flag.is_none() {
+                violations.push(Violation::Node(v, format!("expected a membership flag, got {l:?}")));
+            }
+            flag
+        }))
+        .collect()
+}
+
+/// Edge labels of a node by port, `None` if the node has no valid edge output
+type PortLabels<'a, L> = Vec<Option<&'a [L]>>;
+
+/// Extract the edge labels of the outputs, reporting nodes with a missing or different output and
+/// edges whose endpoints disagree on the label. Returns the labels by node and port, and the edges
+/// with an agreed label as `(u, v, label)` with `u < v`.
+fn edge_labels<'a, L: Label>(ports: &Ports, outputs: &'a Outputs<L>, violations: &mut Vec<Violation>)
+                             -> (PortLabels<'a, L>, Vec<(usize, usize, &'a L)>) {
+    let labels: Vec<_> = outputs
+        .iter()
+        .enumerate()
+        .map(|(v, o)| match o {
+            Some(Output::Edges(l)) if l.len() == ports[v].len() => Some(l.as_slice()),
+            Some(Output::Edges(l)) => {
+                let msg = format!("expected {} edge labels, got {}", ports[v].len(), l.len());
+                violations.push(Violation::Node(v, msg));
+                None
+            }
+            Some(o) => {
+                violations.push(Violation::Node(v, format!("expected an edge output, got {o:?}")));
+                None
+            }
+            None => {
+                violations.push(Violation::Node(v, "no output".into()));
+                None
+            }
+        })
+        .collect();
+
+    let mut agreed = Vec::new();
+    for (u, v) in edges(ports) {
+        if let (Some(lu), Some(lv)) = (labels[u], labels[v]) {
+            // Resolve the ports of the edge at both endpoints, the network is simple
+            let pu = ports[u].iter().position(|n| *n == v).expect("inconsistent ports");
+            let pv = ports[v].iter().position(|n| *n == u).expect("inconsistent ports");
+            match lu[pu] == lv[pv] {
+                true => agreed.push((u, v, &lu[pu])),
+                false => {
+                    let msg = format!("endpoints disagree on the label: {:?} and {:?}", lu[pu], lv[pv]);
+                    violations.push(Violation::Edge(u, v, msg));
+                }
+            }
+        }
+    }
+
+    (labels, agreed)
+}
+
+/// Verify a maximal matching given as edge outputs, see `Problem::MaximalMatching`
+pub fn maximal_matching<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let (labels, agreed) = edge_labels(ports, outputs, &mut violations);
+
+    // Count the matched edges of each node using the labels the endpoints agree on
+    let mut matched = vec![0; ports.len()];
+    for &(u, v, l) in &agreed {
+        match l.membership() {
+            Some(true) => {
+                matched[u] += 1;
+                matched[v] += 1;
+            }
+            Some(false) => (),
+            None => violations.push(Violation::Edge(u, v, format!("expected a membership flag, got {l:?}"))),
+        }
+    }
+
+    for (v, n) in matched.iter().enumerate() {
+        if *n > 1 {
+            violations.push(Violation::Node(v, format!("matched over {n} edges")));
+        }
+    }
+
+    // An edge can only be added to the matching if both endpoints are known to be unmatched
+    for &(u, v, l) in &agreed {
+        if l.membership() == Some(false) && labels[u].is_some() && labels[v].is_some() && matched[u] == 0 && matched[v] == 0 {
+            violations.push(Violation::Edge(u, v, "neither endpoint is matched".into()));
+        }
+    }
+
+    violations
+}
+
+/// Verify a vertex cover given as node outputs, see `Problem::VertexCover`
+pub fn vertex_cover<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let flags = node_flags(outputs, &mut violations);
+
+    for (u, v) in edges(ports) {
+        if flags[u] == Some(false) && flags[v] == Some(false) {
+            violations.push(Violation::Edge(u, v, "not covered".into()));
+        }
+    }
+
+    violations
+}
+
+/// Verify an independent set given as node outputs, see `Problem::IndependentSet`
+pub fn independent_set<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let flags = node_flags(outputs, &mut violations);
+
+    for (u, v) in edges(ports) {
+        if flags[u] == Some(true) && flags[v] == Some(true) {
+            violations.push(Violation::Edge(u, v, "both endpoints are in the set".into()));
+        }
+    }
+
+    violations
+}
+
+/// Verify a maximal independent set given as node outputs, see `Problem::MaximalIndependentSet`
+pub fn maximal_independent_set<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = independent_set(ports, outputs);
+    let flags = node_flags(outputs, &mut Vec::new()); // Output violations are already reported
+
+    for (v, n) in ports.iter().enumerate() {
+        if flags[v] == Some(false) && n.iter().all(|u| flags[*u] == Some(false)) {
+            violations.push(Violation::Node(v, "could be added to the set".into()));
+        }
+    }
+
+    violations
+}
+
+/// Verify a proper vertex colouring given as node outputs, see `Problem::VertexColoring`
+pub fn vertex_coloring<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let colors = node_labels(outputs, &mut violations);
+
+    for (u, v) in edges(ports) {
+        if let (Some(cu), Some(cv)) = (colors[u], colors[v]) {
+            if cu == cv {
+                violations.push(Violation::Edge(u, v, format!("both endpoints have colour {cu:?}")));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Verify a proper edge colouring given as edge outputs, see `Problem::EdgeColoring`
+pub fn edge_coloring<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let (colors, _) = edge_labels(ports, outputs, &mut violations);
+
+    // Disagreeing endpoints are already reported, so it suffices to check the labels of each node
+    for (v, c) in colors.iter().enumerate() {
+        if let Some(c) = c {
+            let mut seen = Vec::new();
+            let mut reported = Vec::new();
+            for l in c.iter() {
+                if seen.contains(&l) && !reported.contains(&l) {
+                    violations.push(Violation::Node(v, format!("multiple incident edges have colour {l:?}")));
+                    reported.push(l);
+                }
+                seen.push(l);
+            }
+        }
+    }
+
+    violations
+}
+
+/// Verify a dominating set given as node outputs, see `Problem::DominatingSet`
+pub fn dominating_set<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let flags = node_flags(outputs, &mut violations);
+
+    for (v, n) in ports.iter().enumerate() {
+        if flags[v] == Some(false) && n.iter().all(|u| flags[*u] == Some(false)) {
+            violations.push(Violation::Node(v, "not dominated".into()));
+        }
+    }
+
+    violations
+}
+
+/// Verify a spanning tree given as edge outputs, see `Problem::SpanningTree`
+pub fn spanning_tree<L: Label>(ports: &Ports, outputs: &Outputs<L>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let (_, agreed) = edge_labels(ports, outputs, &mut violations);
+
+    // Union-find over the nodes for detecting cycles and connectivity
+    let mut parent: Vec<_> = (0..ports.len()).collect();
+    fn find(parent: &mut [usize], mut v: usize) -> usize {
+        while parent[v] != v {
+            parent[v] = parent[parent[v]];
+            v = parent[v];
+        }
+        v
+    }
+
+    for &(u, v, l) in &agreed {
+        match l.membership() {
+            Some(true) => {
+                let (ru, rv) = (find(&mut parent, u), find(&mut parent, v));
+                match ru == rv {
+                    true => violations.push(Violation::Edge(u, v, "closes a cycle".into())),
+                    false => parent[ru] = rv,
+                }
+            }
+            Some(false) => (),
+            None => violations.push(Violation::Edge(u, v, format!("expected a membership flag, got {l:?}"))),
+        }
+    }
+
+    // Only report disconnected nodes if all the edges are known, otherwise the missing ones would
+    // be reported twice
+    if violations.is_empty() {
+        let root = find(&mut parent, 0);
+        for v in 0..ports.len() {
+            if find(&mut parent, v) != root {
+                violations.push(Violation::Node(v, "not connected to node 0 by the tree".into()));
+            }
+        }
+    }
+
+    violations
+}
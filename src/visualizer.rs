@@ -0,0 +1,232 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::io;
+use serde_json::json;
+
+/// Renderable snapshot of the network in a single communication round. This is the common data
+/// model for all the output formats of the simulator.
+pub struct Frame {
+    pub nodes: Vec<NodeView>,
+    pub edges: Vec<EdgeView>,
+}
+
+/// Rendering details of a single node in a frame
+pub struct NodeView {
+    /// `Debug` representation of the state of the node
+    pub label: String,
+    /// Graphviz attributes provided by the state of the node
+    pub attributes: String,
+}
+
+/// Rendering details of a single edge in a frame
+pub struct EdgeView {
+    /// Endpoint nodes of the edge
+    pub nodes: (usize, usize),
+    /// Zero-based port numbers of the edge at both endpoints
+    pub ports: (usize, usize),
+    /// Determines if either endpoint wants to highlight the edge
+    pub highlighted: bool,
+    /// `Debug` representations of the messages sent by both endpoints over the edge, if any
+    pub messages: (Option<String>, Option<String>),
+}
+
+// Canvas size for the layout, matching the `viewBox` of the SVG element in the template
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 700.0;
+const MARGIN: f64 = 50.0;
+
+/// Render the given frames into a self-contained HTML document with no external dependencies.
+/// The document contains a slider for scrubbing through the frames, and shows the states of the
+/// nodes on hover and the messages sent over the edges as labels. Fails if no frames are given.
+pub fn render_html(title: &str, frames: &[Frame]) -> io::Result<String> {
+    let first = frames.first().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no frames given"))?;
+    let layout = layout(first.nodes.len(), &first.edges);
+
+    let data = json!({
+        "title": title,
+        "nodes": layout.iter().map(|(x, y)| [x.round(), y.round()]).collect::<Vec<_>>(),
+        "edges": first.edges.iter().map(|e| [e.nodes.0, e.nodes.1, e.ports.0 + 1, e.ports.1 + 1]).collect::<Vec<_>>(),
+        "frames": frames.iter().map(|f| json!({
+            "labels": f.nodes.iter().map(|n| &n.label).collect::<Vec<_>>(),
+            "fills": f.nodes.iter().map(|n| fill_color(&n.attributes)).collect::<Vec<_>>(),
+            "highlighted": f.edges.iter().map(|e| e.highlighted).collect::<Vec<_>>(),
+            "messages": f.edges.iter().map(|e| [&e.messages.0, &e.messages.1]).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+    });
+
+    // Prevent the data from terminating the script element early
+    let data = data.to_string().replace("</", "<\\/");
+    Ok(TEMPLATE.replacen("/*DATA*/", &data, 1))
+}
+
+/// Extract the fill color from Graphviz node attributes for use in the HTML output
+fn fill_color(attributes: &str) -> Option<String> {
+    let (_, rest) = attributes.split_once("fillcolor")?;
+    let value = rest.trim_start().strip_prefix('=')?.trim_start();
+    let value = value.strip_prefix('"').map_or(value, |v| v.split('"').next().unwrap_or(v));
+    value.split_whitespace().next().map(String::from)
+}
+
+/// Compute a deterministic force-directed (Fruchterman-Reingold) layout for the network
+fn layout(node_count: usize, edges: &[EdgeView]) -> Vec<(f64, f64)> {
+    let (w, h) = (WIDTH - 2.0 * MARGIN, HEIGHT - 2.0 * MARGIN);
+    let k = (w * h / node_count as f64).sqrt();
+    let iterations = if node_count > 500 { 50 } else { 300 };
+
+    // Start from a circular layout to keep the result deterministic
+    let mut pos: Vec<_> = (0..node_count)
+        .map(|i| {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / node_count as f64;
+            (w / 2.0 * (1.0 + 0.9 * angle.cos()), h / 2.0 * (1.0 + 0.9 * angle.sin()))
+        })
+        .collect();
+
+    for iteration in 0..iterations {
+        let mut disp = vec![(0.0, 0.0); node_count];
+
+        // Repulsive forces between all pairs of nodes
+        for i in 0..node_count {
+            for j in i + 1..node_count {
+                let (dx, dy) = (pos[i].0 - pos[j].0, pos[i].1 - pos[j].1);
+                let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = k * k / dist;
+                disp[i].0 += dx / dist * force;
+                disp[i].1 += dy / dist * force;
+                disp[j].0 -= dx / dist * force;
+                disp[j].1 -= dy / dist * force;
+            }
+        }
+
+        // Attractive forces along the edges
+        for (a, b) in edges.iter().map(|e| e.nodes) {
+            let (dx, dy) = (pos[a].0 - pos[b].0, pos[a].1 - pos[b].1);
+            let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+            let force = dist * dist / k;
+            disp[a].0 -= dx / dist * force;
+            disp[a].1 -= dy / dist * force;
+            disp[b].0 += dx / dist * force;
+            disp[b].1 += dy / dist * force;
+        }
+
+        // Move the nodes, limiting the displacement by a linearly decreasing temperature
+        let temperature = w / 10.0 * (1.0 - iteration as f64 / iterations as f64);
+        for (p, (dx, dy)) in pos.iter_mut().zip(disp) {
+            let len = (dx * dx + dy * dy).sqrt().max(0.01);
+            p.0 = (p.0 + dx / len * len.min(temperature)).clamp(0.0, w);
+            p.1 = (p.1 + dy / len * len.min(temperature)).clamp(0.0, h);
+        }
+    }
+
+    pos.into_iter().map(|(x, y)| (x + MARGIN, y + MARGIN)).collect()
+}
+
+/// HTML template of the visualizer, the `/*DATA*/` placeholder is replaced with the network data
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>da-simulator</title>
+<style>
+  body { font-family: sans-serif; margin: 1em; }
+  #controls { display: flex; align-items: center; gap: 1em; margin-bottom: 0.5em; }
+  #round { flex-grow: 1; }
+  svg { width: 100%; max-height: 85vh; border: 1px solid #ccc; }
+  .edge { stroke: #888; stroke-width: 2; }
+  .edge.highlighted { stroke: red; stroke-width: 5; }
+  .node { stroke: black; stroke-width: 1.5; }
+  .label { font-size: 13px; pointer-events: none; }
+  .port { font-size: 10px; fill: #888; pointer-events: none; }
+  .message { font-size: 11px; fill: #0050a0; pointer-events: none; }
+  #tooltip { position: fixed; display: none; background: #ffe; border: 1px solid #999;
+             padding: 0.3em 0.5em; pointer-events: none; white-space: pre; }
+</style>
+</head>
+<body>
+<h2 id="title"></h2>
+<div id="controls">
+  <button id="prev">&lt;</button>
+  <input type="range" id="round" min="0" value="0">
+  <button id="next">&gt;</button>
+  <span id="round-label"></span>
+</div>
+<svg id="network" viewBox="0 0 1000 700"></svg>
+<div id="tooltip"></div>
+<script>
+const data = /*DATA*/;
+const svg = document.getElementById("network");
+const slider = document.getElementById("round");
+const roundLabel = document.getElementById("round-label");
+const tooltip = document.getElementById("tooltip");
+const ns = "http://www.w3.org/2000/svg";
+document.getElementById("title").textContent = data.title;
+slider.max = data.frames.length - 1;
+
+function element(name, attributes, text) {
+  const e = document.createElementNS(ns, name);
+  for (const [k, v] of Object.entries(attributes)) e.setAttribute(k, v);
+  if (text !== undefined) e.textContent = text;
+  svg.appendChild(e);
+  return e;
+}
+
+// Position along the edge from node a towards node b
+function along(a, b, t) {
+  const [xa, ya] = data.nodes[a], [xb, yb] = data.nodes[b];
+  return [xa + (xb - xa) * t, ya + (yb - ya) * t];
+}
+
+const edges = data.edges.map(([a, b, pa, pb]) => {
+  const [xa, ya] = data.nodes[a], [xb, yb] = data.nodes[b];
+  const line = element("line", { class: "edge", x1: xa, y1: ya, x2: xb, y2: yb });
+  const [ta, tb] = [along(a, b, 0.12), along(b, a, 0.12)];
+  element("text", { class: "port", x: ta[0], y: ta[1] }, pa);
+  element("text", { class: "port", x: tb[0], y: tb[1] }, pb);
+  const [ma, mb] = [along(a, b, 0.3), along(b, a, 0.3)];
+  const msgA = element("text", { class: "message", x: ma[0], y: ma[1] });
+  const msgB = element("text", { class: "message", x: mb[0], y: mb[1] });
+  return { line, msgA, msgB };
+});
+
+const nodes = data.nodes.map(([x, y], i) => {
+  const circle = element("circle", { class: "node", cx: x, cy: y, r: 14 });
+  element("text", { class: "label", x: x, y: y + 4, "text-anchor": "middle" }, i);
+  const state = element("text", { class: "label", x: x + 18, y: y - 12 });
+  circle.addEventListener("mousemove", (e) => {
+    tooltip.style.display = "block";
+    tooltip.style.left = (e.clientX + 12) + "px";
+    tooltip.style.top = (e.clientY + 12) + "px";
+    tooltip.textContent = "Node " + i + "\n" + data.frames[slider.value].labels[i];
+  });
+  circle.addEventListener("mouseleave", () => tooltip.style.display = "none");
+  return { circle, state };
+});
+
+function show(round) {
+  const frame = data.frames[round];
+  const last = round == data.frames.length - 1;
+  roundLabel.textContent = last ? "Final states" : "Round " + round;
+  nodes.forEach(({ circle, state }, i) => {
+    circle.setAttribute("fill", frame.fills[i] || "white");
+    state.textContent = frame.labels[i];
+  });
+  edges.forEach(({ line, msgA, msgB }, i) => {
+    line.classList.toggle("highlighted", frame.highlighted[i]);
+    msgA.textContent = frame.messages[i][0] || "";
+    msgB.textContent = frame.messages[i][1] || "";
+  });
+}
+
+slider.addEventListener("input", () => show(slider.value));
+document.getElementById("prev").addEventListener("click", () => { slider.stepDown(); show(slider.value); });
+document.getElementById("next").addEventListener("click", () => { slider.stepUp(); show(slider.value); });
+show(0);
+</script>
+</body>
+</html>
+"#;
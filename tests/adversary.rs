@@ -0,0 +1,142 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+mod common;
+
+use std::time::Duration;
+use rand::Rng;
+use pn_simulator::adversary::*;
+use pn_simulator::algorithms::*;
+use pn_simulator::faults::Crashes;
+use pn_simulator::generators;
+use pn_simulator::*;
+use common::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Adversary telling every neighbor the sum of the states of all nodes
+struct Gossip;
+
+impl Adversary<Sum, Number> for Gossip {
+    fn send(&self, view: &View<Sum>, outbox: &mut Outbox<Number>) {
+        assert_eq!(view.states.len(), view.network.len());
+        outbox.broadcast(Number::Value(view.states.iter().map(|s| s.1).sum()));
+    }
+}
+
+/// Outputs of the neighborhood gathering of the given depth on a cycle with node 0 Byzantine
+fn cycle_sums(depth: u32, adversary: impl Adversary<Sum, Number> + 'static) -> Vec<Option<Output<u32>>> {
+    let mut simulator = DaSimulator::builder(Gather(depth))
+        .network(&generators::cycle(6))
+        .byzantine(&[0], adversary)
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    assert!(simulator.check_scheduling(3, Duration::from_micros(500)).expect("diverged").success());
+    simulator.outputs()
+}
+
+#[test]
+fn silent_byzantine_nodes_need_not_stop() {
+    let mut simulator = DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::cycle(8))
+        .options(Options { byzantine: vec![2, 5], ..Options::default() })
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let result = simulator.run();
+    assert!(result.success() && !result.complete());
+    assert_eq!(result.byzantine_nodes(), 2);
+    assert_eq!(result.stopped, 6);
+    assert_eq!((result.stopping_rounds[2], result.stopping_rounds[5]), (None, None));
+}
+
+#[test]
+fn silent_neighbors_are_absent() {
+    let outputs = cycle_sums(1, Silent);
+    assert_eq!((&outputs[1], &outputs[5]), (&Some(Output::Node(2)), &Some(Output::Node(2))));
+    assert!(outputs[2..5].iter().all(|o| *o == Some(Output::Node(4))));
+}
+
+#[test]
+fn equivocating_nodes_tell_each_port_something_else() {
+    // Node 1 is behind the first port of node 0 and node 5 behind the second one
+    let outputs = cycle_sums(1, Equivocating { messages: vec![Number::Value(10), Number::Value(20)] });
+    assert_eq!((&outputs[1], &outputs[5]), (&Some(Output::Node(12)), &Some(Output::Node(22))));
+    assert!(outputs[2..5].iter().all(|o| *o == Some(Output::Node(4))));
+}
+
+#[test]
+fn adversary_sees_the_states_at_the_beginning_of_the_round() {
+    // All nodes start with a sum of 2, nodes 1 and 5 have a sum of 12 + 2 after the first round
+    // and the others 4, so in the second round node 1 gets 44 from node 0 and 4 from node 2
+    assert_eq!(cycle_sums(2, Gossip)[1], Some(Output::Node(48)));
+}
+
+#[test]
+fn adversary_sees_nodes_crashed_in_the_first_round() {
+    let mut simulator = DaSimulator::builder(Gather(2))
+        .network(&generators::cycle(6))
+        .byzantine(&[0], Gossip)
+        .crashes(Crashes::Schedule(vec![(3, 1)]))
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let result = simulator.run();
+    assert!(result.success() && result.panic.is_none());
+    assert_eq!(result.crashes[3], Some(1));
+
+    // The views include the initial state of node 3, so node 0 tells node 1 the sum 12 in the
+    // first round and 4 + 14 + 2 + 2 + 2 + 14 in the second one, to which node 2 adds 2. Node 2
+    // hears only from node 1 with node 3 absent, i.e. 2 and then 14.
+    let outputs = simulator.outputs();
+    assert_eq!((&outputs[1], &outputs[2]), (&Some(Output::Node(40)), &Some(Output::Node(14))));
+}
+
+#[test]
+fn random_messages_are_reproducible() {
+    let run = |seed| {
+        let random = RandomMessages { generate: |rng| Number::Value(rng.gen_range(0..100)), seed };
+        let mut simulator = DaSimulator::builder(Gather(3))
+            .network(&generators::grid(4, 3))
+            .byzantine(&[1, 6], random)
+            .timeout(TIMEOUT)
+            .jitter(Duration::from_micros(200), seed)
+            .build()
+            .unwrap();
+
+        assert!(simulator.run().success());
+        simulator.outputs()
+    };
+
+    assert_eq!(run(3), run(3));
+    assert_ne!(run(3), run(4));
+}
+
+#[test]
+fn invalid_byzantine_nodes_are_rejected() {
+    let build = |nodes: &[usize]| DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::path(4))
+        .byzantine(nodes, Silent)
+        .build()
+        .err();
+
+    assert!(build(&[4]).is_some());
+    assert!(build(&[0, 3]).is_none());
+
+    // Ports left empty by the adversary would shift the ports of the receivers without a marker
+    let error = DaSimulator::builder(IsomorphicNeighborhood::new(1))
+        .network(&generators::path(4))
+        .byzantine(&[0], Silent)
+        .build()
+        .err();
+    assert!(error.is_some_and(|e| e.contains("absent marker")));
+}
@@ -0,0 +1,105 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use pn_simulator::algorithms::*;
+use pn_simulator::generators;
+use pn_simulator::harness::Harness;
+use pn_simulator::network::{IdAssignment, PortNumbering};
+use pn_simulator::{DaSimulator, Output};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const BOTH_PORTS: &[PortNumbering] = &[PortNumbering::Given, PortNumbering::Random];
+const BOTH_IDS: &[IdAssignment] = &[IdAssignment::Given, IdAssignment::Random];
+
+#[test]
+fn bipartite_matching_is_maximal_within_2_delta_rounds() {
+    // The algorithm requires the network to be bipartite wrt. even/odd nodes, so the IDs are given
+    Harness::new(|| BipartiteMaximalMatching)
+        .family("gnp-bipartite:12,0.3")
+        .family("cycle:10")
+        .family("grid:5,4")
+        .family("path:7")
+        .seeds(0..20)
+        .port_numberings(BOTH_PORTS)
+        .round_bound(|_, max_degree| 2 * max_degree as u32 + 1)
+        .check();
+}
+
+#[test]
+fn bipartite_matching_fails_on_odd_edges() {
+    // Adjacent nodes of the same color never get matched to each other, and adjacent black nodes
+    // wait for each other indefinitely
+    let failure = Harness::new(|| BipartiteMaximalMatching)
+        .family("gnp:10,0.4")
+        .seeds(0..10)
+        .round_bound(|_, max_degree| 2 * max_degree as u32 + 1)
+        .run()
+        .unwrap_err();
+
+    assert_eq!(failure.shrunk.len(), 1, "{failure}");
+}
+
+#[test]
+fn invalid_network_families_fail_the_harness() {
+    let failure = Harness::new(|| BipartiteMaximalMatching)
+        .family("path:4")
+        .family("cycle:2")
+        .run()
+        .unwrap_err();
+
+    assert_eq!((failure.family.as_str(), failure.seed), ("cycle:2", 0));
+    assert!(failure.reason.contains("at least 3 nodes"), "{failure}");
+}
+
+#[test]
+fn mvc_3approx_is_a_vertex_cover_within_2_delta_rounds() {
+    Harness::new(Mvc3approx::default)
+        .family("gnp:12,0.3")
+        .family("random-regular:10,3")
+        .family("complete:6")
+        .family("tree:2,3")
+        .seeds(0..20)
+        .port_numberings(BOTH_PORTS)
+        .id_assignments(BOTH_IDS)
+        .round_bound(|_, max_degree| 2 * max_degree as u32 + 1)
+        .check();
+}
+
+#[test]
+fn mvc_3approx_is_within_factor_3_of_the_optimum() {
+    for seed in 0..20 {
+        let mut simulator = DaSimulator::new(Mvc3approx::default(), &generators::gnp(14, 0.3, seed), TIMEOUT);
+        assert!(simulator.run().success());
+
+        let approximation = simulator.approximation().expect("no approximation");
+        assert!(approximation.ratio() <= 3.0, "seed {seed}: {approximation:?}");
+    }
+}
+
+#[test]
+fn isomorphic_neighborhood_stops_at_the_given_depth() {
+    for depth in 1..5 {
+        Harness::new(move || IsomorphicNeighborhood::new(depth))
+            .family("gnp:10,0.4")
+            .family("torus:3,4")
+            .seeds(0..5)
+            .port_numberings(BOTH_PORTS)
+            .id_assignments(BOTH_IDS)
+            .round_bound(move |_, _| depth)
+            .check();
+    }
+}
+
+#[test]
+fn isomorphic_neighborhood_sums_degrees_on_regular_networks() {
+    // Every node of a d-regular network sees a sum of d^(k + 1) at depth k
+    let mut simulator = DaSimulator::new(IsomorphicNeighborhood::new(3), &generators::random_regular(12, 3, 0), TIMEOUT);
+    assert!(simulator.run().success());
+    assert!(simulator.outputs().into_iter().all(|o| o == Some(Output::Node(81))));
+}
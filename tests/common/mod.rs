@@ -0,0 +1,69 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Fixtures shared by the integration tests
+
+use pn_simulator::*;
+
+/// Isomorphic neighborhood gathering up to the given depth, where absent messages count as zero
+pub struct Gather(pub u32);
+
+/// Number of rounds, sum and target depth like `InState`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sum(pub u32, pub u32, pub u32);
+
+#[derive(Clone, Debug)]
+pub enum Number {
+    Value(u32),
+    Absent,
+}
+
+impl Message for Number {
+    fn absent() -> Option<Self> {
+        Some(Number::Absent)
+    }
+}
+
+impl State for Sum {
+    type Output = u32;
+
+    fn is_output(&self) -> bool {
+        self.0 == self.2
+    }
+
+    fn output(&self) -> Option<Output<u32>> {
+        self.is_output().then_some(Output::Node(self.1))
+    }
+}
+
+impl DistributedAlgorithm<Sum, Number> for Gather {
+    fn name(&self) -> String {
+        "Gather".into()
+    }
+
+    fn init(&self, info: &Input) -> Sum {
+        Sum(0, info.node_degree, self.0)
+    }
+
+    fn send(&self, state: &Sum, outbox: &mut Outbox<Number>) {
+        outbox.broadcast(Number::Value(state.1));
+    }
+
+    fn receive(&self, state: &Sum, messages: impl Iterator<Item=Number>) -> Sum {
+        if state.is_output() {
+            return state.clone();
+        }
+
+        let sum = messages.map(|m| match m {
+            Number::Value(n) => n,
+            Number::Absent => 0,
+        }).sum();
+
+        Sum(state.0 + 1, sum, state.2)
+    }
+}
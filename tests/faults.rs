@@ -0,0 +1,195 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+mod common;
+
+use std::time::Duration;
+use pn_simulator::algorithms::*;
+use pn_simulator::faults::{Crashes, MessageFault, MessageFaults};
+use pn_simulator::generators;
+use pn_simulator::*;
+use common::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn crashed_nodes_freeze_and_the_others_stop() {
+    let mut simulator = DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::cycle(10))
+        .crashes(Crashes::Schedule(vec![(6, 1), (3, 2), (3, 4)]))
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let initial = simulator.states();
+    let result = simulator.run();
+    assert!(result.success() && !result.complete());
+    assert_eq!(result.crashed(), 2);
+    assert_eq!((result.crashes[3], result.crashes[6]), (Some(2), Some(1)));
+    assert_eq!(result.stopped, 8);
+
+    // A node crashing in the first round never leaves its initial state
+    assert_eq!(simulator.states()[6], initial[6]);
+}
+
+#[test]
+fn random_crashes_are_reproducible() {
+    let run = |seed| {
+        let mut simulator = DaSimulator::builder(Mvc3approx::default())
+            .network(&generators::gnp(16, 0.3, 0))
+            .crashes(Crashes::Random { count: 4, max_round: 3, seed })
+            .timeout(TIMEOUT)
+            .round_limit(20)
+            .build()
+            .unwrap();
+
+        // Nodes waiting for a crashed neighbor may never stop, but the rounds are still lock-step
+        let result = simulator.run();
+        assert_eq!(result.crashed(), 4);
+        assert!(result.crashes.iter().flatten().all(|r| (1..=3).contains(r)));
+        (result.crashes, simulator.states())
+    };
+
+    assert_eq!(run(7), run(7));
+}
+
+#[test]
+fn nodes_without_live_neighbors_keep_running() {
+    // The leaves of the star only receive the absent marker after the center crashes
+    let mut simulator = DaSimulator::builder(Gather(3))
+        .network(&generators::star(4))
+        .crashes(Crashes::Schedule(vec![(0, 2)]))
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let result = simulator.run();
+    assert!(result.success());
+    assert_eq!(result.stopping_rounds[1..], [Some(3); 4]);
+}
+
+#[test]
+fn invalid_crashes_are_rejected() {
+    let build = |crashes| DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::path(4))
+        .crashes(crashes)
+        .build()
+        .err();
+
+    assert!(build(Crashes::Schedule(vec![(4, 1)])).is_some());
+    assert!(build(Crashes::Schedule(vec![(0, 0)])).is_some());
+    assert!(build(Crashes::Random { count: 5, max_round: 1, seed: 0 }).is_some());
+    assert!(build(Crashes::Random { count: 4, max_round: 1, seed: 0 }).is_none());
+}
+
+#[test]
+fn crashes_require_an_absent_marker() {
+    // Leaving out the messages of crashed neighbors would shift the ports of the receivers
+    let build = |crashes| DaSimulator::builder(IsomorphicNeighborhood::new(2))
+        .network(&generators::path(4))
+        .crashes(crashes)
+        .build()
+        .err();
+
+    assert!(build(Crashes::Schedule(vec![(1, 2)])).is_some_and(|e| e.contains("absent marker")));
+    assert!(build(Crashes::Random { count: 0, max_round: 1, seed: 0 }).is_none());
+}
+
+/// Outputs of the neighborhood gathering of depth 1 on a cycle with the given message faults
+fn cycle_sums(faults: MessageFaults) -> (Vec<Option<Output<u32>>>, u64) {
+    let mut simulator = DaSimulator::builder(Gather(1))
+        .network(&generators::cycle(6))
+        .message_faults(faults)
+        .corruption(|_, _| Number::Value(100))
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let result = simulator.run();
+    assert!(result.success());
+    (simulator.outputs(), result.faults.total())
+}
+
+#[test]
+fn scheduled_message_faults_affect_only_the_receiver() {
+    let (expected, _) = cycle_sums(MessageFaults::default());
+    assert!(expected.iter().all(|o| *o == Some(Output::Node(4))));
+
+    // The dropped message is replaced by the absent marker counting as zero, the corrupted one by
+    // the result of the corruption function
+    for (fault, sum) in [(MessageFault::Drop, 2), (MessageFault::Corrupt, 102)] {
+        let (outputs, count) = cycle_sums(MessageFaults { schedule: vec![(0, 1, 1, fault)], ..MessageFaults::default() });
+        assert_eq!(count, 1);
+        assert_eq!(outputs[1], Some(Output::Node(sum)), "{fault}");
+        assert_eq!(outputs[2..], expected[2..]);
+    }
+}
+
+#[test]
+fn duplicated_messages_replace_the_next_ones() {
+    // The message of node 0 in the first round reaches node 1 again in the second round
+    let faults = MessageFaults { schedule: vec![(0, 1, 1, MessageFault::Duplicate)], ..MessageFaults::default() };
+    let mut simulator = DaSimulator::builder(IsomorphicNeighborhood::new(2))
+        .network(&generators::cycle(6))
+        .message_faults(faults)
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    let result = simulator.run();
+    assert_eq!(result.faults.duplicated, 1);
+    assert_eq!(simulator.outputs()[1], Some(Output::Node(6)));
+}
+
+#[test]
+fn random_message_faults_are_reproducible() {
+    let run = |seed| {
+        let edges = generators::grid(4, 3);
+        let faulty = edges.iter().step_by(3).map(|(a, b)| (*a as usize, *b as usize)).collect();
+        let faults = MessageFaults { drop: 0.2, duplicate: 0.1, edges: faulty, seed, ..MessageFaults::default() };
+        let mut simulator = DaSimulator::builder(BipartiteMaximalMatching)
+            .network(&edges)
+            .message_faults(faults)
+            .timeout(TIMEOUT)
+            .round_limit(20)
+            .jitter(Duration::from_micros(200), seed)
+            .build()
+            .unwrap();
+
+        let result = simulator.run();
+        assert!(result.faults.total() > 0);
+        (simulator.states(), result.rounds, result.faults)
+    };
+
+    assert_eq!(run(1), run(1));
+}
+
+#[test]
+fn unsupported_message_faults_are_rejected() {
+    let duplicate = MessageFaults { duplicate: 0.5, ..MessageFaults::default() };
+    let options = Options { message_faults: duplicate.clone(), ..Options::default() };
+    let build = |options| DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::path(4))
+        .options(options)
+        .build()
+        .err();
+
+    // Duplication requires a clone function and corruption requires a corruption function
+    assert!(build(options).is_some());
+    assert!(build(Options { message_faults: MessageFaults { corrupt: 0.5, ..duplicate.clone() }, ..Options::default() }).is_some());
+    assert!(build(Options { message_faults: MessageFaults { drop: 0.6, ..duplicate }, ..Options::default() }).is_some());
+    assert!(build(Options { message_faults: MessageFaults { edges: vec![(0, 2)], ..MessageFaults::default() }, ..Options::default() }).is_some());
+
+    // Dropping requires an absent marker for not shifting the ports of the receiver
+    let error = DaSimulator::builder(IsomorphicNeighborhood::new(1))
+        .network(&generators::path(4))
+        .message_faults(MessageFaults { drop: 0.1, ..MessageFaults::default() })
+        .build()
+        .err();
+    assert!(error.is_some_and(|e| e.contains("absent marker")));
+}
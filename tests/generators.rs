@@ -0,0 +1,41 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashSet;
+use pn_simulator::generators::{self, EdgeSet};
+use pn_simulator::network;
+
+/// Check that the given network is simple and every node has the given degree
+fn assert_regular(edges: &EdgeSet, n: u32, d: u32) {
+    let uniq: HashSet<_> = edges.iter().map(|&(a, b)| (a.min(b), a.max(b))).collect();
+    assert_eq!(uniq.len(), edges.len(), "parallel edges");
+    assert!(edges.iter().all(|(a, b)| a != b), "self-loop");
+
+    let mut degrees = vec![0; n as usize];
+    edges.iter().flat_map(|(a, b)| [a, b]).for_each(|v| degrees[*v as usize] += 1);
+    assert_eq!(degrees, vec![d; n as usize]);
+}
+
+#[test]
+fn random_regular_graphs_are_simple_and_regular() {
+    for (n, d) in [(12, 3), (30, 25), (31, 30), (20, 19), (16, 9)] {
+        let edges = generators::random_regular(n, d, 1);
+        assert_regular(&edges, n, d);
+        assert_eq!(edges, generators::random_regular(n, d, 1), "not reproducible");
+    }
+}
+
+#[test]
+fn invalid_generator_parameters_are_errors() {
+    for spec in ["cycle:2", "hypercube:40", "random-regular:5,3", "gnp:3,0.0", "gnp:10,1.5", "grid:70000,70000", "tree:1000,10"] {
+        assert!(network::generate(spec, 0).is_err(), "{spec}");
+    }
+
+    assert_eq!(network::generate("cycle:3", 0), Ok(generators::cycle(3)));
+    assert_eq!(network::generate("gnp:10,0.3", 4), Ok(generators::gnp(10, 0.3, 4)));
+}
@@ -0,0 +1,125 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::Duration;
+use pn_simulator::algorithms::*;
+use pn_simulator::generators;
+use pn_simulator::lift::Lift;
+use pn_simulator::{DaSimulator, Output, State};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// State with an internal tag that does not affect the output
+#[derive(Clone, Debug, PartialEq)]
+struct Tagged(u32, bool);
+
+impl State for Tagged {
+    type Output = bool;
+
+    fn is_output(&self) -> bool {
+        true
+    }
+
+    fn output(&self) -> Option<Output<bool>> {
+        Some(Output::Node(self.1))
+    }
+}
+
+/// Degrees of the nodes of the given network by node index
+fn degrees(edges: &[(u32, u32)]) -> Vec<u32> {
+    let mut degrees = vec![0; 1 + edges.iter().map(|(a, b)| *a.max(b) as usize).max().unwrap_or(0)];
+    edges.iter().flat_map(|(a, b)| [a, b]).for_each(|v| degrees[*v as usize] += 1);
+    degrees
+}
+
+/// Check that the lift maps every node to its image preserving the degree
+fn assert_covering(lift: &Lift, base: &[(u32, u32)]) {
+    let (base_degrees, degrees) = (degrees(base), degrees(lift.edges()));
+    assert_eq!(lift.edges().len(), base.len() * lift.copies() as usize);
+    assert_eq!(lift.covering_map().len(), degrees.len());
+    for (v, image) in lift.covering_map().iter().enumerate() {
+        assert_eq!(*image, v as u32 / lift.copies());
+        assert_eq!(degrees[v], base_degrees[*image as usize]);
+    }
+}
+
+#[test]
+fn k_lifts_follow_the_permutations() {
+    // Crossing a single edge of the triangle turns the 2-lift into a 6-cycle
+    let base = generators::cycle(3);
+    let lift = Lift::new(&base, 2, &[vec![0, 1], vec![0, 1], vec![1, 0]]);
+    assert_covering(&lift, &base);
+    assert_eq!(lift.edges(), [(0, 2), (1, 3), (2, 4), (3, 5), (0, 5), (1, 4)]);
+    assert!(lift.is_simple());
+
+    // The identity permutations give disjoint copies of the base
+    let lift = Lift::new(&base, 3, &vec![vec![0, 1, 2]; 3]);
+    assert_covering(&lift, &base);
+    assert!(lift.edges().iter().all(|(a, b)| a % 3 == b % 3));
+}
+
+#[test]
+fn random_lifts_are_simple_and_reproducible() {
+    let base = generators::gnp(8, 0.5, 1);
+    let lift = Lift::random(&base, 4, 7);
+    assert_covering(&lift, &base);
+    assert!(lift.is_simple());
+    assert_eq!(lift.edges(), Lift::random(&base, 4, 7).edges());
+
+    // Parallel edges of a multigraph base have to be lifted to different copies
+    let multigraph = [(0, 1), (0, 1), (0, 1)];
+    let lift = Lift::random(&multigraph, 3, 0);
+    assert_covering(&lift, &multigraph);
+    assert!(lift.is_simple());
+}
+
+#[test]
+fn bipartite_double_covers_cross_between_the_copies() {
+    // A triangle with a parallel edge, whose double cover keeps the parallel edges
+    let multigraph = [(0, 1), (1, 2), (0, 2), (0, 1)];
+    let lift = Lift::bipartite_double_cover(&multigraph);
+    assert_covering(&lift, &multigraph);
+    assert!(lift.edges().iter().all(|(a, b)| a % 2 != b % 2));
+    assert!(!lift.is_simple());
+
+    let lift = Lift::bipartite_double_cover(&generators::cycle(5));
+    assert!(lift.is_simple() && lift.edges().iter().all(|(a, b)| a % 2 != b % 2));
+}
+
+#[test]
+fn lifts_are_indistinguishable_in_a_run() {
+    let base = generators::complete(4);
+    let lift = Lift::random(&base, 3, 2);
+
+    let mut simulator = DaSimulator::new(Mvc3approx::default(), &base, TIMEOUT);
+    assert!(simulator.run().success());
+    let mut lifted = DaSimulator::new(Mvc3approx::default(), lift.edges(), TIMEOUT);
+    assert!(lifted.run().success());
+    assert_eq!(lift.verify(&simulator.states(), &lifted.states()), Ok(()));
+
+    // The base of the random lift of a multigraph cannot be simulated, but its fibers can be
+    let lift = Lift::random(&[(0, 1), (0, 1), (1, 2), (1, 2), (0, 2)], 3, 5);
+    let mut lifted = DaSimulator::new(IsomorphicNeighborhood::new(3), lift.edges(), TIMEOUT);
+    assert!(lifted.run().success());
+    assert_eq!(lift.verify_fibers(&lifted.states()), Ok(()));
+}
+
+#[test]
+fn only_the_outputs_are_compared() {
+    let lift = Lift::bipartite_double_cover(&generators::path(2));
+    let base = [Tagged(0, true), Tagged(0, false)];
+
+    // The tags differ from the base, but only the outputs of nodes 1 and 2 differ
+    let lifted = [Tagged(1, true), Tagged(2, false), Tagged(3, true), Tagged(4, false)];
+    assert_eq!(lift.verify(&base, &lifted), Err(vec![1, 2]));
+    assert_eq!(lift.verify_fibers(&lifted), Err(vec![1, 3]));
+
+    let lifted = [Tagged(1, true), Tagged(2, true), Tagged(3, false), Tagged(4, false)];
+    assert_eq!(lift.verify(&base, &lifted), Ok(()));
+    assert_eq!(lift.verify_fibers(&lifted), Ok(()));
+}
@@ -0,0 +1,33 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::fs;
+use std::io::ErrorKind;
+use std::time::Duration;
+use pn_simulator::algorithms::*;
+use pn_simulator::generators;
+use pn_simulator::DaSimulator;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn frames_require_a_recorded_trace() {
+    let dir = std::env::temp_dir().join(format!("pn-simulator-frames-{}", std::process::id()));
+    let mut simulator = DaSimulator::new(Mvc3approx::default(), &generators::cycle(6), TIMEOUT);
+    assert!(simulator.run().success());
+    let error = simulator.write_frames(&dir, true).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert!(!dir.exists());
+
+    let mut simulator = DaSimulator::new(Mvc3approx::default(), &generators::cycle(6), TIMEOUT);
+    simulator.record_trace(true);
+    let result = simulator.run();
+    assert_eq!(simulator.write_frames(&dir, true).unwrap(), result.rounds as usize + 1);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), result.rounds as usize + 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,152 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::time::{Duration, Instant};
+use pn_simulator::algorithms::{InMessage, IsomorphicNeighborhood};
+use pn_simulator::generators;
+use pn_simulator::simulator::Call;
+use pn_simulator::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Broken algorithm where the center of a star fails in the second round and the leaves never stop
+struct Fragile {
+    forget_port: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Count(u32, u32);
+
+#[derive(Debug)]
+struct Value(Option<u32>);
+
+impl Message for Value {}
+
+impl State for Count {
+    type Output = u32;
+
+    fn is_output(&self) -> bool {
+        false
+    }
+
+    fn output(&self) -> Option<Output<u32>> {
+        None
+    }
+}
+
+/// Broken algorithm where the nodes stop in the round given by their degree but keep counting the
+/// rounds
+struct Restless;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Rounds(u32, u32);
+
+impl State for Rounds {
+    type Output = u32;
+
+    fn is_output(&self) -> bool {
+        self.0 >= self.1
+    }
+
+    fn output(&self) -> Option<Output<u32>> {
+        self.is_output().then_some(Output::Node(self.0))
+    }
+}
+
+impl DistributedAlgorithm<Rounds, Value> for Restless {
+    fn name(&self) -> String {
+        "Restless".into()
+    }
+
+    fn init(&self, info: &Input) -> Rounds {
+        Rounds(0, info.node_degree)
+    }
+
+    fn send(&self, _: &Rounds, outbox: &mut Outbox<Value>) {
+        (0..outbox.ports()).for_each(|p| outbox.send(p, Value(None)));
+    }
+
+    fn receive(&self, state: &Rounds, _: impl Iterator<Item=Value>) -> Rounds {
+        Rounds(state.0 + 1, state.1)
+    }
+}
+
+impl DistributedAlgorithm<Count, Value> for Fragile {
+    fn name(&self) -> String {
+        "Fragile".into()
+    }
+
+    fn init(&self, info: &Input) -> Count {
+        Count(info.node_degree, 0)
+    }
+
+    fn send(&self, state: &Count, outbox: &mut Outbox<Value>) {
+        let ports = match self.forget_port && state.0 > 1 && state.1 > 0 {
+            true => outbox.ports() - 1,
+            false => outbox.ports(),
+        };
+
+        (0..ports).for_each(|p| outbox.send(p, Value((state.1 == 0).then_some(state.0))));
+    }
+
+    fn receive(&self, state: &Count, messages: impl Iterator<Item=Value>) -> Count {
+        Count(state.0, state.1 + messages.map(|m| m.0.unwrap()).sum::<u32>())
+    }
+}
+
+#[test]
+fn receive_panic_is_captured() {
+    let start = Instant::now();
+    let mut simulator = DaSimulator::new(Fragile { forget_port: false }, &generators::star(3), TIMEOUT);
+    let result = simulator.run();
+    assert!(start.elapsed() < TIMEOUT, "the neighbors waited for the timeout");
+    assert!(!result.success());
+
+    // Every node panics in the second round, the earliest one is reported
+    let panic = result.panic.expect("no panic captured");
+    assert_eq!((panic.node, panic.round, panic.call), (0, 2, Call::Receive));
+    assert_eq!(panic.state, "Count(3, 3)");
+    assert_eq!(panic.messages, ["Value(None)"; 3]);
+    assert!(panic.message.contains("`None`"));
+}
+
+#[test]
+fn send_panic_is_captured() {
+    let mut simulator = DaSimulator::new(Fragile { forget_port: true }, &generators::star(3), TIMEOUT);
+    let panic = simulator.run().panic.expect("no panic captured");
+    assert_eq!((panic.node, panic.round, panic.call), (0, 2, Call::Send));
+    assert!(panic.messages.is_empty());
+    assert_eq!(panic.message, "no message sent to port 2");
+}
+
+#[test]
+fn bandwidth_violation_is_captured() {
+    let mut simulator = DaSimulator::builder(IsomorphicNeighborhood::new(3))
+        .network(&generators::cycle(5))
+        .message_size(|InMessage::Number(n)| 32 - n.leading_zeros())
+        .bandwidth(2)
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap();
+
+    // The sums exceed 3 after the first round
+    let result = simulator.run();
+    let panic = result.panic.expect("no panic captured");
+    assert_eq!((panic.round, panic.call), (2, Call::Send));
+    assert_eq!(panic.message, "message Number(4) exceeds the bandwidth of 2 bits");
+}
+
+#[test]
+fn post_stop_transition_is_captured() {
+    // The leaves of the path stop in the first round, but the center only in the second one
+    let mut simulator = DaSimulator::new(Restless, &generators::path(3), TIMEOUT);
+    let panic = simulator.run().panic.expect("no panic captured");
+    assert_eq!((panic.node, panic.round, panic.call), (0, 2, Call::Receive));
+    assert_eq!(panic.state, "Rounds(1, 1)");
+    assert_eq!(panic.message, "detected post-stop state transition to Rounds(2, 1)");
+}
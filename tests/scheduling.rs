@@ -0,0 +1,105 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use pn_simulator::algorithms::*;
+use pn_simulator::faults::{Crashes, MessageFaults};
+use pn_simulator::generators;
+use pn_simulator::*;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const JITTER: Duration = Duration::from_micros(500);
+
+/// Broken algorithm that records the order in which the nodes of a network of the given size
+/// receive their first messages. Every node counts once per run, so the count modulo the size is
+/// the rank of the node within the current run.
+struct Arrival(u32, AtomicU32);
+
+#[derive(Clone, Debug, PartialEq)]
+struct Order(Option<u32>);
+
+#[derive(Clone, Debug)]
+struct Ping;
+
+impl Message for Ping {}
+
+impl State for Order {
+    type Output = u32;
+
+    fn is_output(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn output(&self) -> Option<Output<u32>> {
+        self.0.map(Output::Node)
+    }
+}
+
+impl DistributedAlgorithm<Order, Ping> for Arrival {
+    fn name(&self) -> String {
+        "Arrival".into()
+    }
+
+    fn init(&self, _: &Input) -> Order {
+        Order(None)
+    }
+
+    fn send(&self, _: &Order, outbox: &mut Outbox<Ping>) {
+        outbox.fill(Ping);
+    }
+
+    fn receive(&self, state: &Order, _: impl Iterator<Item=Ping>) -> Order {
+        Order(state.0.or_else(|| Some(self.1.fetch_add(1, Ordering::SeqCst) % self.0)))
+    }
+}
+
+#[test]
+fn included_algorithms_are_independent_of_scheduling() {
+    let mut simulator = DaSimulator::new(BipartiteMaximalMatching, &generators::grid(5, 4), TIMEOUT);
+    assert!(simulator.check_scheduling(5, JITTER).expect("diverged").success());
+
+    let mut simulator = DaSimulator::new(Mvc3approx::default(), &generators::gnp(12, 0.3, 0), TIMEOUT);
+    assert!(simulator.check_scheduling(5, JITTER).expect("diverged").success());
+
+    let mut simulator = DaSimulator::new(IsomorphicNeighborhood::new(3), &generators::torus(3, 4), TIMEOUT);
+    assert!(simulator.check_scheduling(5, JITTER).expect("diverged").success());
+}
+
+#[test]
+fn faulty_runs_are_independent_of_scheduling() {
+    // The number of rounds, messages and faulty messages are compared as well
+    let mut simulator = DaSimulator::builder(BipartiteMaximalMatching)
+        .network(&generators::grid(5, 4))
+        .crashes(Crashes::Schedule(vec![(7, 2), (12, 3)]))
+        .message_faults(MessageFaults { drop: 0.1, duplicate: 0.1, seed: 3, ..MessageFaults::default() })
+        .timeout(TIMEOUT)
+        .round_limit(30)
+        .build()
+        .unwrap();
+
+    let result = simulator.check_scheduling(5, JITTER).expect("diverged");
+    assert!(result.faults.total() > 0);
+}
+
+#[test]
+fn scheduling_dependent_states_diverge() {
+    // Only the order of arrival differs between the runs, which the jitter perturbs
+    let mut simulator = DaSimulator::new(Arrival(12, AtomicU32::new(0)), &generators::cycle(12), TIMEOUT);
+    let divergence = simulator.check_scheduling(5, JITTER).expect_err("no divergence");
+    assert!(!divergence.nodes.is_empty() && divergence.totals.is_empty());
+    assert!(divergence.to_string().contains(&format!("- node {}: Order(Some(", divergence.nodes[0].node)));
+
+    // The ranks of the diverging run are a permutation of the nodes like in the first run
+    let mut ranks: Vec<_> = simulator.outputs().into_iter().map(|o| o.expect("no output")).collect();
+    ranks.sort_by_key(|o| match o {
+        Output::Node(r) => *r,
+        _ => unreachable!(),
+    });
+    assert_eq!(ranks, (0..12).map(Output::Node).collect::<Vec<_>>());
+}
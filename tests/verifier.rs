@@ -0,0 +1,116 @@
+/*
+ * (c) Dennis Marttinen 2022
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use pn_simulator::Output::{self, *};
+use pn_simulator::optimum;
+use pn_simulator::verifier::{Problem, Violation};
+
+/// Triangle 0-1-2 with a pendant node 3 attached to node 2
+fn ports() -> Vec<Vec<usize>> {
+    vec![vec![1, 2], vec![0, 2], vec![0, 1, 3], vec![2]]
+}
+
+fn nodes<L: Clone>(labels: &[L]) -> Vec<Option<Output<L>>> {
+    labels.iter().map(|l| Some(Node(l.clone()))).collect()
+}
+
+fn edges<L: Clone>(labels: &[&[L]]) -> Vec<Option<Output<L>>> {
+    labels.iter().map(|l| Some(Edges(l.to_vec()))).collect()
+}
+
+#[test]
+fn vertex_cover() {
+    assert_eq!(Problem::VertexCover.verify(&ports(), &nodes(&[true, false, true, false])), Ok(()));
+    assert_eq!(Problem::VertexCover.verify(&ports(), &nodes(&[true, false, false, true])),
+               Err(vec![Violation::Edge(1, 2, "not covered".into())]));
+}
+
+#[test]
+fn independent_sets() {
+    let set = nodes(&[true, false, false, true]);
+    assert_eq!(Problem::IndependentSet.verify(&ports(), &set), Ok(()));
+    assert_eq!(Problem::MaximalIndependentSet.verify(&ports(), &set), Ok(()));
+
+    let set = nodes(&[true, false, false, false]);
+    assert_eq!(Problem::IndependentSet.verify(&ports(), &set), Ok(()));
+    assert_eq!(Problem::MaximalIndependentSet.verify(&ports(), &set),
+               Err(vec![Violation::Node(3, "could be added to the set".into())]));
+
+    assert_eq!(Problem::IndependentSet.verify(&ports(), &nodes(&[true, true, false, false])),
+               Err(vec![Violation::Edge(0, 1, "both endpoints are in the set".into())]));
+}
+
+#[test]
+fn dominating_set() {
+    assert_eq!(Problem::DominatingSet.verify(&ports(), &nodes(&[false, false, true, false])), Ok(()));
+    assert_eq!(Problem::DominatingSet.verify(&ports(), &nodes(&[true, false, false, false])),
+               Err(vec![Violation::Node(3, "not dominated".into())]));
+}
+
+#[test]
+fn colorings() {
+    assert_eq!(Problem::VertexColoring.verify(&ports(), &nodes(&[0, 1, 2, 0])), Ok(()));
+    assert_eq!(Problem::VertexColoring.verify(&ports(), &nodes(&[0, 1, 2, 2])),
+               Err(vec![Violation::Edge(2, 3, "both endpoints have colour 2".into())]));
+
+    assert_eq!(Problem::EdgeColoring.verify(&ports(), &edges(&[&[0, 1], &[0, 2], &[1, 2, 0], &[0]])), Ok(()));
+    assert_eq!(Problem::EdgeColoring.verify(&ports(), &edges(&[&[0, 1], &[0, 2], &[1, 2, 1], &[1]])),
+               Err(vec![Violation::Node(2, "multiple incident edges have colour 1".into())]));
+}
+
+#[test]
+fn maximal_matching() {
+    let matching = edges(&[&[false, false], &[false, true], &[false, true, false], &[false]]);
+    assert_eq!(Problem::MaximalMatching.verify(&ports(), &matching), Ok(()));
+
+    // Node 3 claims the edge to node 2, but node 2 is matched to node 1
+    let disagreement = edges(&[&[false, false], &[false, true], &[false, true, false], &[true]]);
+    assert_eq!(Problem::MaximalMatching.verify(&ports(), &disagreement),
+               Err(vec![Violation::Edge(2, 3, "endpoints disagree on the label: false and true".into())]));
+
+    let not_maximal = edges(&[&[false, false], &[false, false], &[false, false, true], &[true]]);
+    assert_eq!(Problem::MaximalMatching.verify(&ports(), &not_maximal),
+               Err(vec![Violation::Edge(0, 1, "neither endpoint is matched".into())]));
+}
+
+#[test]
+fn spanning_tree() {
+    let tree = edges(&[&[true, true], &[true, false], &[true, false, true], &[true]]);
+    assert_eq!(Problem::SpanningTree.verify(&ports(), &tree), Ok(()));
+
+    let cycle = edges(&[&[true, true], &[true, true], &[true, true, false], &[false]]);
+    assert_eq!(Problem::SpanningTree.verify(&ports(), &cycle),
+               Err(vec![Violation::Edge(1, 2, "closes a cycle".into())]));
+
+    let forest = edges(&[&[true, false], &[true, false], &[false, false, true], &[true]]);
+    assert_eq!(Problem::SpanningTree.verify(&ports(), &forest), Err(vec![
+        Violation::Node(2, "not connected to node 0 by the tree".into()),
+        Violation::Node(3, "not connected to node 0 by the tree".into()),
+    ]));
+}
+
+#[test]
+fn missing_and_mismatched_outputs() {
+    let outputs = vec![Some(Node(true)), None, Some(Edges(vec![true])), Some(Node(false))];
+    assert_eq!(Problem::VertexCover.verify(&ports(), &outputs), Err(vec![
+        Violation::Node(1, "no output".into()),
+        Violation::Node(2, "expected a node output, got Edges([true])".into()),
+    ]));
+}
+
+#[test]
+fn exact_optima() {
+    assert_eq!(optimum::min_vertex_cover(&ports()), 2);
+    assert_eq!(optimum::max_matching(&ports()), 2);
+    assert_eq!(optimum::min_dominating_set(&ports()), 1);
+    assert_eq!(optimum::chromatic_number(&ports()), 3);
+
+    assert_eq!(Problem::VertexCover.value(&nodes(&[true, false, true, true])), Some(3));
+    assert_eq!(Problem::VertexColoring.value(&nodes(&[0, 1, 2, 0])), Some(3));
+    assert_eq!(Problem::IndependentSet.optimum(&ports()), None);
+}